@@ -6,35 +6,208 @@ use proc_macro_error::*;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput};
 
-#[proc_macro_derive(WebScraper, attributes(on_html, on_page, on_response))]
+#[proc_macro_derive(
+    WebScraper,
+    attributes(
+        on_html,
+        on_page,
+        on_response,
+        on_sitemap_entry,
+        on_feed_item,
+        on_structured_data,
+        on_record,
+        on_error,
+        on_start,
+        on_finish,
+        on_url
+    )
+)]
 #[proc_macro_error]
 /// Macro to derive WebScraper trait on to a given struct.
 /// Supported options:
-/// * `#[on_page(method_name)]` - will bind given method to a successful page load with the
-///   contents of the page
+/// * `#[on_page(method_name)]` - will bind given method to a successful page load, method can
+///   take `Page` with url, headers and parsed document, `Response` or contents of the page as `String`
 /// * `#[on_html("css selector", method_name)]` - will bind given css selector to a method. When page
 ///   is loaded this method will be invoked for all elements that match given selector.
+///   When several handlers share a selector, the first one in scope is invoked.
 /// * `#[on_response(method_name)]` - will bind given method to an HTTP response
+/// * `#[on_url("pattern", method_name)]` - will bind given method to HTTP responses from urls that
+///   match glob or regex pattern, see `url_matches` for the syntax. Invalid regular expressions
+///   are reported at compile time
+/// * `on_html` and `on_page` accept optional `url = "pattern"` argument, handler is invoked only on
+///   pages with matching url
+/// * `#[on_sitemap_entry(method_name)]` - will bind given method to every url found in a sitemap,
+///   url is visited only if method returns `true`
+/// * `#[on_feed_item(method_name)]` - will bind given method to every item of RSS or Atom feed
+/// * `#[on_structured_data(method_name)]` - will bind given method to JSON-LD, microdata and
+///   OpenGraph/Twitter card data extracted from every loaded page
+/// * `#[on_record(RecordType, "css selector", method_name)]` - will build `RecordType` out of every
+///   element that matches given selector and pass it to a method, `RecordType` has to implement
+///   `Extract` trait
+/// * `#[on_error(method_name)]` - will bind given method to errors returned by other handlers
+///   when `ErrorPolicy::Handler` is used, method gets `ErrorContext` and the error. Without it
+///   such errors abort the crawl
+/// * `#[on_start(method_name)]` - will bind given method to the start of the crawl, it runs
+///   before seed urls are queued and gets `Response` that can be used to queue requests
+/// * `#[on_finish(method_name)]` - will bind given method to the end of the crawl, it runs once
+///   all work is done or crawl failed and gets final `CrawlStats`
+///
+/// Generic structs are supported, type parameters, lifetimes and where-clauses are carried over
+/// to the generated implementation.
 pub fn web_scraper_derive(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = parse_macro_input!(input as DeriveInput);
 
     match ast.data {
         syn::Data::Struct(syn::DataStruct { .. }) => impl_web_scraper(&ast),
-        _ => abort_call_site!("#[WebScraper] only supports structs"),
+        _ => abort!(ast.ident, "#[derive(WebScraper)] only supports structs"),
     }
 }
 
+#[proc_macro_derive(Extract, attributes(css))]
+#[proc_macro_error]
+/// Macro to derive Extract trait on to a struct with named fields.
+/// Every field marked with `#[css(...)]` is parsed out of the element using `FromStr`,
+/// `Option<T>` fields are allowed to be missing and `Vec<T>` fields collect all matches,
+/// fields without the attribute are set to `Default::default()`.
+/// Supported options:
+/// * `#[css("css selector")]` or `#[css("css selector", text)]` - text of matching element
+/// * `#[css("css selector", attr = "name")]` - attribute of matching element
+/// * `#[css(attr = "name")]` - attribute of the element itself
+pub fn extract_derive(input: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = parse_macro_input!(input as DeriveInput);
+
+    match &ast.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => impl_extract(&ast, fields),
+        _ => abort_call_site!("#[derive(Extract)] only supports structs with named fields"),
+    }
+}
+
+fn impl_extract(ast: &syn::DeriveInput, fields: &syn::FieldsNamed) -> TokenStream {
+    use syn::*;
+
+    let name = &ast.ident;
+    let mut inits = vec![];
+
+    for field in &fields.named {
+        let ident = field.ident.as_ref().unwrap();
+        let css = field.attrs.iter().find(|attr| attr.path.is_ident("css"));
+
+        let init = match css.map(|attr| attr.parse_meta()) {
+            None => quote! { #ident: Default::default() },
+            Some(Ok(Meta::List(MetaList { nested, .. }))) => {
+                let values = handle_css_attr(nested);
+                let field_name = ident.to_string();
+                let extractor = match field_kind(&field.ty) {
+                    "Option" => quote! { crabler::extract::optional },
+                    "Vec" => quote! { crabler::extract::all },
+                    _ => quote! { crabler::extract::required },
+                };
+
+                quote! { #ident: #extractor(#field_name, #values)? }
+            }
+            Some(Err(err)) => abort!(field, "Failed to parse attribute: {}", err),
+            Some(_) => abort!(field, "Unsupported arguments on css attribute"),
+        };
+
+        inits.push(init);
+    }
+
+    let gen = quote! {
+        impl Extract for #name {
+            fn extract(element: &Element) -> std::result::Result<Self, CrablerError> {
+                Ok(#name {
+                    #( #inits, )*
+                })
+            }
+        }
+    };
+
+    gen.into()
+}
+
+fn handle_css_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> proc_macro2::TokenStream {
+    use syn::*;
+
+    let mut selector = quote! { None };
+    let mut attr = quote! { None };
+
+    for (i, meta) in nested.iter().enumerate() {
+        match meta {
+            NestedMeta::Lit(Lit::Str(lit_str)) if i == 0 => selector = quote! { Some(#lit_str) },
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("text") => attr = quote! { None },
+            NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+                path,
+                lit: Lit::Str(lit_str),
+                ..
+            })) if path.is_ident("attr") => attr = quote! { Some(#lit_str) },
+            _ => abort!(
+                meta,
+                "Expected selector, `text` or `attr = \"name\"` in css attribute"
+            ),
+        }
+    }
+
+    quote! { crabler::extract::values(element, #selector, #attr) }
+}
+
+/// Name of the outer type for `Option<T>` and `Vec<T>` fields
+fn field_kind(ty: &syn::Type) -> &'static str {
+    if let syn::Type::Path(syn::TypePath { path, .. }) = ty {
+        match path.segments.last() {
+            Some(segment) if segment.ident == "Option" => return "Option",
+            Some(segment) if segment.ident == "Vec" => return "Vec",
+            _ => {}
+        }
+    }
+
+    ""
+}
+
+/// Attributes handled by WebScraper derive, anything else on the struct is left alone
+const HANDLER_ATTRIBUTES: &[&str] = &[
+    "on_html",
+    "on_record",
+    "on_response",
+    "on_page",
+    "on_sitemap_entry",
+    "on_feed_item",
+    "on_structured_data",
+    "on_error",
+    "on_start",
+    "on_finish",
+    "on_url",
+];
+
 fn impl_web_scraper(ast: &syn::DeriveInput) -> TokenStream {
     use syn::*;
 
     let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
 
     let mut pages = vec![];
-    let mut selectors = vec![];
-    let mut matches = vec![];
+    let mut html_handlers = vec![];
     let mut responses = vec![];
+    let mut urls = vec![];
+    let mut sitemap_entries = vec![];
+    let mut feed_items = vec![];
+    let mut structured_data = vec![];
+    let mut errors = vec![];
+    let mut starts = vec![];
+    let mut finishes = vec![];
 
     for attr in &ast.attrs {
+        if !HANDLER_ATTRIBUTES
+            .iter()
+            .any(|name| attr.path.is_ident(name))
+        {
+            continue;
+        }
+
         let meta = attr.parse_meta();
 
         match meta {
@@ -47,9 +220,14 @@ fn impl_web_scraper(ast: &syn::DeriveInput) -> TokenStream {
             Ok(Meta::List(MetaList { path, nested, .. }))
                 if path.segments[0].ident == "on_html" =>
             {
-                let (selector, match_clause) = handle_on_html_attr(nested);
-                selectors.push(selector);
-                matches.push(match_clause);
+                let (selector, handler) = handle_on_html_attr(nested);
+                add_html_handler(&mut html_handlers, selector, handler);
+            }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_record" =>
+            {
+                let (selector, handler) = handle_on_record_attr(nested);
+                add_html_handler(&mut html_handlers, selector, handler);
             }
             Ok(Meta::List(MetaList { path, nested, .. }))
                 if path.segments[0].ident == "on_response" =>
@@ -57,21 +235,87 @@ fn impl_web_scraper(ast: &syn::DeriveInput) -> TokenStream {
                 let response = handle_on_response_attr(nested);
                 responses.push(response);
             }
+            Ok(Meta::List(MetaList { path, nested, .. })) if path.segments[0].ident == "on_url" => {
+                let url = handle_on_url_attr(nested);
+                urls.push(url);
+            }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_sitemap_entry" =>
+            {
+                let sitemap_entry = handle_on_sitemap_entry_attr(nested);
+                sitemap_entries.push(sitemap_entry);
+            }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_feed_item" =>
+            {
+                let feed_item = handle_on_feed_item_attr(nested);
+                feed_items.push(feed_item);
+            }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_structured_data" =>
+            {
+                let data = handle_on_structured_data_attr(nested);
+                structured_data.push(data);
+            }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_error" =>
+            {
+                if !errors.is_empty() {
+                    abort_call_site!("Only one on_error attribute is allowed");
+                }
+                let error = handle_on_error_attr(nested);
+                errors.push(error);
+            }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_start" =>
+            {
+                if !starts.is_empty() {
+                    abort_call_site!("Only one on_start attribute is allowed");
+                }
+                let start = handle_on_start_attr(nested);
+                starts.push(start);
+            }
+            Ok(Meta::List(MetaList { path, nested, .. }))
+                if path.segments[0].ident == "on_finish" =>
+            {
+                if !finishes.is_empty() {
+                    abort_call_site!("Only one on_finish attribute is allowed");
+                }
+                let finish = handle_on_finish_attr(nested);
+                finishes.push(finish);
+            }
             Err(err) => {
-                abort_call_site!("Failed to parse attribute: {}", err);
+                abort!(attr, "Failed to parse attribute: {}", err);
             }
             _ => {
-                abort_call_site!("Unsupported arguments on attribute");
+                abort!(
+                    attr,
+                    "Unsupported arguments on attribute, expected #[{}(...)]",
+                    attr.path.get_ident().unwrap()
+                );
             }
         }
     }
 
+    let on_error = errors.pop().unwrap_or_else(|| quote! { Err(error) });
+    let selectors = html_handlers
+        .iter()
+        .map(|(selector, _)| quote! { #selector })
+        .collect::<Vec<_>>();
+    let matches = html_handlers
+        .iter()
+        .map(|(selector, handlers)| {
+            let dispatch = html_dispatch(handlers);
+            quote! { #selector => #dispatch }
+        })
+        .collect::<Vec<_>>();
+
     let gen = quote! {
         #[async_trait(?Send)]
-        impl WebScraper for #name {
+        impl #impl_generics WebScraper for #name #ty_generics #where_clause {
             async fn dispatch_on_page(
                 &mut self,
-                page: String,
+                page: Page,
             ) -> std::result::Result<(), CrablerError> {
                 #( #pages; )*
 
@@ -84,13 +328,10 @@ fn impl_web_scraper(ast: &syn::DeriveInput) -> TokenStream {
                 request: Response,
                 element: Element,
             ) -> std::result::Result<(), CrablerError> {
-
                 match selector {
                     #( #matches, )*
                     _ => panic!("Failed to dispatch {}", selector),
-                };
-
-                Ok(())
+                }
             }
 
             fn all_html_selectors(&self) -> Vec<&str> {
@@ -101,28 +342,100 @@ fn impl_web_scraper(ast: &syn::DeriveInput) -> TokenStream {
                 &mut self,
                 request: Response,
             ) -> std::result::Result<(), CrablerError> {
+                #( #urls; )*
                 #( #responses; )*
 
                 Ok(())
             }
 
+            async fn dispatch_on_sitemap_entry(
+                &mut self,
+                entry: &SitemapEntry,
+            ) -> std::result::Result<bool, CrablerError> {
+                #(
+                    if !#sitemap_entries {
+                        return Ok(false);
+                    }
+                )*
+
+                Ok(true)
+            }
+
+            async fn dispatch_on_feed_item(
+                &mut self,
+                request: Response,
+                item: FeedItem,
+            ) -> std::result::Result<(), CrablerError> {
+                #( #feed_items; )*
+
+                Ok(())
+            }
+
+            async fn dispatch_on_structured_data(
+                &mut self,
+                request: Response,
+                document: &Document,
+            ) -> std::result::Result<(), CrablerError> {
+                #( #structured_data; )*
+
+                Ok(())
+            }
+
+            async fn dispatch_on_error(
+                &mut self,
+                context: ErrorContext,
+                error: CrablerError,
+            ) -> std::result::Result<(), CrablerError> {
+                #on_error
+            }
+
+            async fn dispatch_on_start(
+                &mut self,
+                request: Response,
+            ) -> std::result::Result<(), CrablerError> {
+                #( #starts; )*
+
+                Ok(())
+            }
+
+            async fn dispatch_on_finish(
+                &mut self,
+                stats: &CrawlStats,
+            ) -> std::result::Result<(), CrablerError> {
+                #( #finishes; )*
+
+                Ok(())
+            }
+
             async fn run(
                 self,
                 opts: Opts,
             ) -> std::result::Result<(), CrablerError> {
+                self.run_with_stats(opts).await?;
+
+                Ok(())
+            }
+
+            async fn run_with_stats(
+                self,
+                opts: Opts,
+            ) -> std::result::Result<CrawlStats, CrablerError> {
                 use crabler::Crabler;
 
-                let mut crabler = Crabler::new(self, &opts);
+                let mut crabler = Crabler::from_opts(self, &opts)?;
+                crabler.start(&opts).await
+            }
 
-                for url in &opts.urls {
-                    crabler.navigate(url).await?;
-                }
+            async fn crawl(
+                self,
+                opts: Opts,
+            ) -> std::result::Result<Self, CrablerError> {
+                use crabler::Crabler;
 
-                for _ in 0..opts.threads {
-                    crabler.start_worker();
-                }
+                let mut crabler = Crabler::from_opts(self, &opts)?;
+                crabler.start(&opts).await?;
 
-                crabler.run().await
+                Ok(crabler.into_scraper())
             }
         }
     };
@@ -145,12 +458,105 @@ fn handle_on_page_attr(
         _ => abort_call_site!("Can't find on_page method"),
     };
 
-    quote! { self.#f(page).await? }
+    match url_scope(&nested) {
+        Some(scope) => {
+            let matches = url_match(&scope, quote! { &page.url });
+
+            quote! {
+                if #matches {
+                    self.#f(FromPage::from_page(&page)).await?
+                }
+            }
+        }
+        None => quote! { self.#f(FromPage::from_page(&page)).await? },
+    }
+}
+
+/// Optional url scope of html handler and expression calling it
+type HtmlHandler = (Option<syn::LitStr>, proc_macro2::TokenStream);
+
+/// Group handlers by selector so that every selector is dispatched once
+fn add_html_handler(
+    html_handlers: &mut Vec<(syn::LitStr, Vec<HtmlHandler>)>,
+    selector: syn::LitStr,
+    handler: HtmlHandler,
+) {
+    match html_handlers
+        .iter_mut()
+        .find(|(existing, _)| existing.value() == selector.value())
+    {
+        Some((_, handlers)) => handlers.push(handler),
+        None => html_handlers.push((selector, vec![handler])),
+    }
+}
+
+/// Optional `url = "pattern"` argument of an attribute
+fn url_scope(
+    nested: &syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> Option<syn::LitStr> {
+    use syn::*;
+
+    nested.iter().find_map(|meta| match meta {
+        NestedMeta::Meta(Meta::NameValue(MetaNameValue {
+            path,
+            lit: Lit::Str(lit_str),
+            ..
+        })) if path.is_ident("url") => Some(validate_url_pattern(lit_str)),
+        _ => None,
+    })
+}
+
+/// Regular expressions are checked at compile time, globs are always valid
+fn validate_url_pattern(pattern: &syn::LitStr) -> syn::LitStr {
+    let value = pattern.value();
+
+    if value.starts_with('^') {
+        if let Err(e) = regex::Regex::new(&value) {
+            abort!(pattern, "Invalid url pattern {}: {}", value, e);
+        }
+    }
+
+    pattern.clone()
+}
+
+/// Expression matching url against pattern that is compiled once per attribute
+fn url_match(pattern: &syn::LitStr, url: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote! {
+        {
+            static PATTERN: std::sync::OnceLock<crabler::UrlPattern> = std::sync::OnceLock::new();
+            PATTERN.get_or_init(|| {
+                crabler::UrlPattern::new(#pattern).expect("url pattern is validated by derive")
+            })
+        }
+        .is_match(#url)
+    }
+}
+
+/// Chain handlers of one selector so that the first one in scope is invoked,
+/// handlers after the first unscoped one can never run
+fn html_dispatch(handlers: &[HtmlHandler]) -> proc_macro2::TokenStream {
+    handlers
+        .iter()
+        .rev()
+        .fold(quote! { Ok(()) }, |rest, (scope, call)| match scope {
+            Some(scope) => {
+                let matches = url_match(scope, quote! { &request.url });
+
+                quote! {
+                    if #matches {
+                        #call
+                    } else {
+                        #rest
+                    }
+                }
+            }
+            None => quote! { #call },
+        })
 }
 
 fn handle_on_html_attr(
     nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
-) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+) -> (syn::LitStr, HtmlHandler) {
     use syn::*;
 
     let l = nested.len();
@@ -168,10 +574,50 @@ fn handle_on_html_attr(
         _ => abort_call_site!("Can't find on_html method"),
     };
 
-    let selector = quote! { #token };
-    let match_clause = quote! { #token => self.#f(request, element).await? };
+    let handler = (
+        url_scope(&nested),
+        quote! { self.#f(request, element).await },
+    );
+
+    (token.clone(), handler)
+}
+
+fn handle_on_record_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> (syn::LitStr, HtmlHandler) {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 3 {
+        abort_call_site!(
+            "Not enough arguments provided to on_record attribute: {}",
+            l
+        );
+    }
+
+    let record = match &nested[0] {
+        NestedMeta::Meta(Meta::Path(path)) => path,
+        _ => abort_call_site!("Can't find on_record type"),
+    };
+
+    let token = match &nested[1] {
+        NestedMeta::Lit(Lit::Str(lit_str)) => lit_str,
+        _ => abort_call_site!("Can't find on_record selector"),
+    };
+
+    let f = match &nested[2] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Can't find on_record method"),
+    };
 
-    (selector, match_clause)
+    let call = quote! {
+        {
+            let record = <#record as Extract>::extract(&element)?;
+            self.#f(request, record).await
+        }
+    };
+
+    (token.clone(), (None, call))
 }
 
 fn handle_on_response_attr(
@@ -194,3 +640,152 @@ fn handle_on_response_attr(
 
     quote! { self.#f(request).await? }
 }
+
+fn handle_on_sitemap_entry_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> proc_macro2::TokenStream {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 1 {
+        abort_call_site!(
+            "Not enough arguments provided to on_sitemap_entry attribute: {}",
+            l
+        );
+    }
+
+    let f = match &nested[0] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Can't find on_sitemap_entry method"),
+    };
+
+    quote! { self.#f(entry).await? }
+}
+
+fn handle_on_feed_item_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> proc_macro2::TokenStream {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 1 {
+        abort_call_site!(
+            "Not enough arguments provided to on_feed_item attribute: {}",
+            l
+        );
+    }
+
+    let f = match &nested[0] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Can't find on_feed_item method"),
+    };
+
+    quote! { self.#f(request, item).await? }
+}
+
+fn handle_on_structured_data_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> proc_macro2::TokenStream {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 1 {
+        abort_call_site!(
+            "Not enough arguments provided to on_structured_data attribute: {}",
+            l
+        );
+    }
+
+    let f = match &nested[0] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Can't find on_structured_data method"),
+    };
+
+    quote! { self.#f(request, StructuredData::extract(document)).await? }
+}
+
+fn handle_on_error_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> proc_macro2::TokenStream {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 1 {
+        abort_call_site!("Not enough arguments provided to on_error attribute: {}", l);
+    }
+
+    let f = match &nested[0] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Can't find on_error method"),
+    };
+
+    quote! { self.#f(context, error).await }
+}
+
+fn handle_on_start_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> proc_macro2::TokenStream {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 1 {
+        abort_call_site!("Not enough arguments provided to on_start attribute: {}", l);
+    }
+
+    let f = match &nested[0] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Can't find on_start method"),
+    };
+
+    quote! { self.#f(request).await? }
+}
+
+fn handle_on_finish_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> proc_macro2::TokenStream {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 1 {
+        abort_call_site!(
+            "Not enough arguments provided to on_finish attribute: {}",
+            l
+        );
+    }
+
+    let f = match &nested[0] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Can't find on_finish method"),
+    };
+
+    quote! { self.#f(stats.clone()).await? }
+}
+
+fn handle_on_url_attr(
+    nested: syn::punctuated::Punctuated<syn::NestedMeta, syn::token::Comma>,
+) -> proc_macro2::TokenStream {
+    use syn::*;
+
+    let l = nested.len();
+    if l < 2 {
+        abort_call_site!("Not enough arguments provided to on_url attribute: {}", l);
+    }
+
+    let pattern = match &nested[0] {
+        NestedMeta::Lit(Lit::Str(lit_str)) => validate_url_pattern(lit_str),
+        _ => abort_call_site!("Can't find on_url pattern"),
+    };
+
+    let f = match &nested[1] {
+        NestedMeta::Meta(Meta::Path(Path { segments, .. })) => &segments[0].ident,
+        _ => abort_call_site!("Can't find on_url method"),
+    };
+
+    let matches = url_match(&pattern, quote! { &request.url });
+
+    quote! {
+        if #matches {
+            self.#f(request.clone()).await?
+        }
+    }
+}
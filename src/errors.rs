@@ -19,6 +19,47 @@ pub enum CrablerError {
 
     #[error("body parsing error: {0}")]
     BodyParsing(String),
+
+    #[error("invalid url {0}: {1}")]
+    InvalidUrl(String, String),
+
+    #[error("failed to extract field {0}: {1}")]
+    Extract(String, String),
+
+    #[error("json error {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("output error {0}")]
+    Output(String),
+
+    #[error("invalid warc archive: {0}")]
+    Warc(String),
+
+    #[error("invalid url pattern {0}: {1}")]
+    UrlPattern(String, String),
+
+    #[error("handler panicked on {url}: {payload}")]
+    HandlerPanicked { url: String, payload: String },
+}
+
+impl CrablerError {
+    /// Name of error variant, used to group errors in crawl stats
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io(_) => "Io",
+            Self::AsyncRecvError(_) => "AsyncRecvError",
+            Self::AsyncSendError(_) => "AsyncSendError",
+            Self::SurfError(..) => "SurfError",
+            Self::BodyParsing(_) => "BodyParsing",
+            Self::InvalidUrl(..) => "InvalidUrl",
+            Self::Extract(..) => "Extract",
+            Self::Json(_) => "Json",
+            Self::Output(_) => "Output",
+            Self::Warc(_) => "Warc",
+            Self::UrlPattern(..) => "UrlPattern",
+            Self::HandlerPanicked { .. } => "HandlerPanicked",
+        }
+    }
 }
 
 impl<T: Debug> From<SendError<T>> for CrablerError {
@@ -33,4 +74,15 @@ impl From<surf::Error> for CrablerError {
     }
 }
 
+/// Where handler error happened, passed to `on_error` handler
+#[derive(Clone, Debug)]
+pub struct ErrorContext {
+    /// Url of response that was being processed
+    pub url: String,
+    /// Name of failing hook, e.g. `on_html`, or `pipeline` for item processors and sinks
+    pub hook: &'static str,
+    /// Selector of failing `on_html` or `on_record` handler
+    pub selector: Option<String>,
+}
+
 pub type Result<T> = std::result::Result<T, CrablerError>;
@@ -0,0 +1,84 @@
+//! Typed records built out of html elements.
+//! Functions in this module are used by code generated with `#[derive(Extract)]`.
+
+use crate::errors::*;
+use crabquery::Element;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// Record that can be built out of html element, usually derived with `#[derive(Extract)]`
+pub trait Extract: Sized {
+    fn extract(element: &Element) -> Result<Self>;
+}
+
+/// Text or attribute values of elements that match selector inside of given element,
+/// without selector value of element itself is used
+#[doc(hidden)]
+pub fn values(element: &Element, selector: Option<&str>, attr: Option<&str>) -> Vec<String> {
+    match selector {
+        Some(selector) => element
+            .select(selector)
+            .iter()
+            .filter_map(|el| value(el, attr))
+            .collect(),
+        None => value(element, attr).into_iter().collect(),
+    }
+}
+
+#[doc(hidden)]
+pub fn required<T>(field: &str, values: Vec<String>) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    match values.into_iter().next() {
+        Some(value) => parse(field, value),
+        None => Err(CrablerError::Extract(
+            field.to_string(),
+            "no matching element".to_string(),
+        )),
+    }
+}
+
+#[doc(hidden)]
+pub fn optional<T>(field: &str, values: Vec<String>) -> Result<Option<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    values
+        .into_iter()
+        .next()
+        .map(|value| parse(field, value))
+        .transpose()
+}
+
+#[doc(hidden)]
+pub fn all<T>(field: &str, values: Vec<String>) -> Result<Vec<T>>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    values
+        .into_iter()
+        .map(|value| parse(field, value))
+        .collect()
+}
+
+fn value(el: &Element, attr: Option<&str>) -> Option<String> {
+    match attr {
+        Some(attr) => el.attr(attr),
+        None => el.text(),
+    }
+    .map(|value| value.trim().to_string())
+}
+
+fn parse<T>(field: &str, value: String) -> Result<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    value
+        .parse()
+        .map_err(|e| CrablerError::Extract(field.to_string(), format!("{:?}: {}", value, e)))
+}
@@ -0,0 +1,148 @@
+use crate::errors::*;
+use crate::sitemap::parse_w3c_datetime;
+use chrono::{DateTime, Utc};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+/// Single RSS 2.0 `<item>` or Atom `<entry>`
+#[derive(Clone, Debug, Default)]
+pub struct FeedItem {
+    pub title: Option<String>,
+    pub link: Option<String>,
+    pub published: Option<DateTime<Utc>>,
+    pub guid: Option<String>,
+}
+
+impl FeedItem {
+    fn set(&mut self, field: &[u8], value: &str) {
+        match field {
+            b"title" => self.title = Some(value.to_string()),
+            // RSS keeps link as text, Atom uses href attribute handled separately
+            b"link" => self.link = Some(value.to_string()),
+            b"guid" | b"id" => self.guid = Some(value.to_string()),
+            b"pubDate" => {
+                self.published = DateTime::parse_from_rfc2822(value)
+                    .ok()
+                    .map(|datetime| datetime.with_timezone(&Utc))
+            }
+            b"published" => self.published = parse_w3c_datetime(value),
+            b"updated" if self.published.is_none() => self.published = parse_w3c_datetime(value),
+            _ => {}
+        }
+    }
+
+    fn set_atom_link(&mut self, link: &BytesStart) {
+        let mut href = None;
+        let mut alternate = true;
+
+        for attr in link.attributes().flatten() {
+            match attr.key.local_name().as_ref() {
+                b"href" => href = attr.unescape_value().ok().map(|v| v.to_string()),
+                b"rel" => alternate = attr.value.as_ref() == b"alternate",
+                _ => {}
+            }
+        }
+
+        if alternate && self.link.is_none() {
+            self.link = href;
+        }
+    }
+}
+
+/// Feeds are recognized by content type, generic xml responses are sniffed
+/// for `<rss>` or `<feed>` root element
+pub(crate) fn is_feed(content_type: Option<&str>, body: &str) -> bool {
+    match content_type {
+        Some("application/rss+xml") | Some("application/atom+xml") => true,
+        Some("application/xml") | Some("text/xml") | None => root_is_feed(body),
+        Some(_) => false,
+    }
+}
+
+fn root_is_feed(body: &str) -> bool {
+    let mut reader = Reader::from_str(body);
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                return matches!(e.local_name().as_ref(), b"rss" | b"feed");
+            }
+            Ok(Event::Eof) | Err(_) => return false,
+            _ => {}
+        }
+    }
+}
+
+/// Parse items of RSS 2.0 or Atom feed
+pub(crate) fn parse_feed(body: &str) -> Result<Vec<FeedItem>> {
+    let mut reader = Reader::from_str(body);
+    reader.trim_text(true);
+
+    let mut items = vec![];
+    let mut depth = 0;
+    let mut item: Option<(usize, FeedItem)> = None;
+    let mut field: Option<Vec<u8>> = None;
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                depth += 1;
+                let name = e.local_name();
+
+                match item.as_mut() {
+                    None if name.as_ref() == b"item" || name.as_ref() == b"entry" => {
+                        item = Some((depth, FeedItem::default()));
+                    }
+                    Some((item_depth, parsed)) if depth == *item_depth + 1 => {
+                        if name.as_ref() == b"link" {
+                            parsed.set_atom_link(&e);
+                        }
+                        field = Some(name.as_ref().to_vec());
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::Empty(e)) => {
+                if let Some((item_depth, parsed)) = item.as_mut() {
+                    if depth == *item_depth && e.local_name().as_ref() == b"link" {
+                        parsed.set_atom_link(&e);
+                    }
+                }
+            }
+            Ok(Event::Text(text)) => {
+                if let (Some((_, parsed)), Some(field)) = (item.as_mut(), &field) {
+                    let value = text
+                        .unescape()
+                        .map_err(|e| CrablerError::BodyParsing(format!("feed: {}", e)))?;
+                    parsed.set(field, value.trim());
+                }
+            }
+            Ok(Event::CData(text)) => {
+                if let (Some((_, parsed)), Some(field)) = (item.as_mut(), &field) {
+                    let value = String::from_utf8_lossy(&text);
+                    parsed.set(field, value.trim());
+                }
+            }
+            Ok(Event::End(_)) => {
+                match item.take() {
+                    Some((item_depth, parsed)) if item_depth == depth => items.push(parsed),
+                    other => item = other,
+                }
+
+                field = None;
+                depth -= 1;
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(CrablerError::BodyParsing(format!(
+                    "feed at position {}: {}",
+                    reader.buffer_position(),
+                    e
+                )))
+            }
+            _ => {}
+        }
+    }
+
+    Ok(items)
+}
@@ -0,0 +1,241 @@
+use crate::errors::*;
+use async_trait::async_trait;
+use futures::io::{AsyncRead, AsyncReadExt, Cursor};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Request made by worker, crabler only ever issues GET requests
+#[derive(Clone, Debug)]
+pub struct FetchRequest {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl FetchRequest {
+    /// GET request without any extra headers
+    pub fn get(url: &str) -> Self {
+        FetchRequest {
+            url: url.to_string(),
+            headers: vec![],
+        }
+    }
+
+    /// First value of header with given name, names are case insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+/// Body of fetched response, read lazily by worker
+pub type Body = Box<dyn AsyncRead + Send + Unpin>;
+
+/// Response returned by fetcher, body is not read until worker needs it
+pub struct FetchResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Body,
+}
+
+impl fmt::Debug for FetchResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FetchResponse")
+            .field("status", &self.status)
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+impl FetchResponse {
+    pub fn new(
+        status: u16,
+        headers: Vec<(String, String)>,
+        body: impl AsyncRead + Send + Unpin + 'static,
+    ) -> Self {
+        FetchResponse {
+            status,
+            headers,
+            body: Box::new(body),
+        }
+    }
+
+    /// Response with body that is already in memory
+    pub fn from_bytes(status: u16, headers: Vec<(String, String)>, body: Vec<u8>) -> Self {
+        Self::new(status, headers, Cursor::new(body))
+    }
+
+    /// First value of header with given name, names are case insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Mime type of response without parameters, e.g. `text/html`
+    pub fn content_type(&self) -> Option<String> {
+        self.header("content-type")
+            .and_then(|value| value.split(';').next())
+            .map(|essence| essence.trim().to_lowercase())
+            .filter(|essence| !essence.is_empty())
+    }
+
+    /// Read whole body into memory, body can be read again afterwards
+    pub async fn bytes(&mut self) -> Result<Vec<u8>> {
+        let mut body = vec![];
+        self.body.read_to_end(&mut body).await?;
+        self.body = Box::new(Cursor::new(body.clone()));
+
+        Ok(body)
+    }
+
+    /// Read whole body into memory without keeping a copy, body is empty afterwards
+    pub(crate) async fn take_bytes(&mut self) -> Result<Vec<u8>> {
+        let mut body = vec![];
+        self.body.read_to_end(&mut body).await?;
+
+        Ok(body)
+    }
+}
+
+pub(crate) fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+/// Transport used by workers to fetch pages, sitemaps and downloads
+#[async_trait]
+pub trait Fetcher: Send + Sync + 'static {
+    async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse>;
+}
+
+#[async_trait]
+impl Fetcher for Box<dyn Fetcher> {
+    async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse> {
+        (**self).fetch(request).await
+    }
+}
+
+#[async_trait]
+impl Fetcher for Arc<dyn Fetcher> {
+    async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse> {
+        (**self).fetch(request).await
+    }
+}
+
+/// Default fetcher backed by `surf` http client
+#[derive(Clone, Debug)]
+pub struct SurfFetcher {
+    client: surf::Client,
+}
+
+impl SurfFetcher {
+    pub fn new(follow_redirects: bool) -> Self {
+        let client = if follow_redirects {
+            surf::client().with(surf::middleware::Redirect::default())
+        } else {
+            surf::client()
+        };
+
+        SurfFetcher { client }
+    }
+}
+
+impl Default for SurfFetcher {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+#[async_trait]
+impl Fetcher for SurfFetcher {
+    async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse> {
+        let mut builder = self.client.get(&request.url);
+        for (name, value) in request.headers.iter() {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+
+        let mut response = builder.await?;
+        let headers = response
+            .iter()
+            .flat_map(|(name, values)| {
+                values
+                    .iter()
+                    .map(move |value| (name.to_string(), value.to_string()))
+            })
+            .collect();
+
+        Ok(FetchResponse::new(
+            response.status().into(),
+            headers,
+            response.take_body(),
+        ))
+    }
+}
+
+#[derive(Clone, Debug)]
+struct MemoryPage {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// Fetcher serving responses from a map of urls, meant for tests.
+/// Urls that are not in the map respond with 404.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryFetcher {
+    pages: HashMap<String, MemoryPage>,
+}
+
+impl MemoryFetcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve html page with status 200 at given url
+    pub fn with_page(self, url: &str, html: &str) -> Self {
+        self.with_response(
+            url,
+            200,
+            vec![("content-type", "text/html")],
+            html.as_bytes().to_vec(),
+        )
+    }
+
+    /// Serve arbitrary response at given url
+    pub fn with_response(
+        self,
+        url: &str,
+        status: u16,
+        headers: Vec<(&str, &str)>,
+        body: Vec<u8>,
+    ) -> Self {
+        let mut new = self;
+        let headers = headers
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        new.pages.insert(
+            url.to_string(),
+            MemoryPage {
+                status,
+                headers,
+                body,
+            },
+        );
+        new
+    }
+}
+
+#[async_trait]
+impl Fetcher for MemoryFetcher {
+    async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse> {
+        let response = match self.pages.get(&request.url) {
+            Some(page) => {
+                FetchResponse::from_bytes(page.status, page.headers.clone(), page.body.clone())
+            }
+            None => FetchResponse::from_bytes(404, vec![], vec![]),
+        };
+
+        Ok(response)
+    }
+}
@@ -0,0 +1,409 @@
+use crate::stats::Stats;
+use crate::{Channels, CrablerError, Job, Navigation, Opts, Result, WorkInput};
+use async_std::channel::{bounded, RecvError};
+use async_std::fs::{File, OpenOptions};
+use async_std::io::BufReader;
+use async_std::prelude::*;
+use async_std::sync::{Mutex as AsyncMutex, RwLock};
+use serde_json::{json, Value};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering as AtomicOrdering};
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// Scoring function used by best-first strategy, higher score is visited first
+pub type ScoreFn = Arc<dyn Fn(&str, usize) -> f64 + Send + Sync>;
+
+/// Order in which queued urls are visited,
+/// explicit priority given to `Response::navigate_with_priority` always wins
+#[derive(Clone, Default)]
+pub enum Strategy {
+    /// Shallow pages first, pages of the same depth in the order they were queued
+    #[default]
+    BreadthFirst,
+    /// Deepest pages first, most recently queued first
+    DepthFirst,
+    /// Pages with highest score first, score is computed from url and depth
+    BestFirst(ScoreFn),
+}
+
+impl Strategy {
+    /// Best-first strategy driven by given scoring function
+    pub fn best_first(score: impl Fn(&str, usize) -> f64 + Send + Sync + 'static) -> Self {
+        let score: ScoreFn = Arc::new(score);
+
+        Strategy::BestFirst(score)
+    }
+
+    fn score(&self, url: &str, depth: usize) -> f64 {
+        match self {
+            Self::BreadthFirst => -(depth as f64),
+            Self::DepthFirst => depth as f64,
+            Self::BestFirst(score) => score(url, depth),
+        }
+    }
+
+    fn tiebreak(&self, seq: u64) -> i64 {
+        match self {
+            Self::DepthFirst => seq as i64,
+            _ => -(seq as i64),
+        }
+    }
+}
+
+impl fmt::Debug for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BreadthFirst => write!(f, "BreadthFirst"),
+            Self::DepthFirst => write!(f, "DepthFirst"),
+            Self::BestFirst(_) => write!(f, "BestFirst"),
+        }
+    }
+}
+
+struct Entry {
+    priority: i32,
+    score: f64,
+    tiebreak: i64,
+    job: Job<WorkInput>,
+}
+
+impl Entry {
+    fn key(&self) -> (i32, f64, i64) {
+        (self.priority, self.score, self.tiebreak)
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (priority, score, tiebreak) = self.key();
+        let (other_priority, other_score, other_tiebreak) = other.key();
+
+        priority
+            .cmp(&other_priority)
+            .then(score.total_cmp(&other_score))
+            .then(tiebreak.cmp(&other_tiebreak))
+    }
+}
+
+/// Entries that did not fit into memory, stored as JSON lines
+/// and moved back in the order they were written
+struct Spill {
+    path: PathBuf,
+    writer: Option<File>,
+    reader: Option<BufReader<File>>,
+}
+
+impl Spill {
+    fn new(path: PathBuf) -> Self {
+        Spill {
+            path,
+            writer: None,
+            reader: None,
+        }
+    }
+
+    async fn write(&mut self, workinput: &WorkInput, depth: usize, priority: i32) -> Result<()> {
+        let line = match workinput {
+            WorkInput::Navigate(url, navigation) => json!({
+                "navigate": url,
+                "meta": navigation.meta,
+                "handler": navigation.handler,
+            }),
+            WorkInput::Download { url, destination } => {
+                json!({ "download": url, "destination": destination })
+            }
+            WorkInput::Sitemap(url) => json!({ "sitemap": url }),
+            WorkInput::DiscoverSitemaps(url) => json!({ "discover_sitemaps": url }),
+            WorkInput::Exit => json!({ "exit": true }),
+        };
+        let mut line = json!({ "depth": depth, "priority": priority, "input": line }).to_string();
+        line.push('\n');
+
+        if self.writer.is_none() {
+            let writer = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .truncate(true)
+                .open(&self.path)
+                .await?;
+            self.reader = Some(BufReader::new(File::open(&self.path).await?));
+            self.writer = Some(writer);
+        }
+
+        if let Some(writer) = self.writer.as_mut() {
+            writer.write_all(line.as_bytes()).await?;
+            // reader uses its own handle, it only sees flushed lines
+            writer.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Read next spilled entry, caller makes sure there is one
+    async fn read(&mut self) -> Result<Option<(WorkInput, usize, i32)>> {
+        let reader = match self.reader.as_mut() {
+            Some(reader) => reader,
+            None => return Ok(None),
+        };
+
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+
+        let value: Value = serde_json::from_str(&line)?;
+        let input = &value["input"];
+        let string = |key: &str| input[key].as_str().map(|s| s.to_string());
+
+        let workinput = if let Some(url) = string("navigate") {
+            let navigation = Navigation {
+                meta: input["meta"].clone(),
+                handler: string("handler"),
+            };
+            WorkInput::Navigate(url, navigation)
+        } else if let (Some(url), Some(destination)) = (string("download"), string("destination")) {
+            WorkInput::Download { url, destination }
+        } else if let Some(url) = string("sitemap") {
+            WorkInput::Sitemap(url)
+        } else if let Some(url) = string("discover_sitemaps") {
+            WorkInput::DiscoverSitemaps(url)
+        } else {
+            WorkInput::Exit
+        };
+
+        let depth = value["depth"].as_u64().unwrap_or_default() as usize;
+        let priority = value["priority"].as_i64().unwrap_or_default() as i32;
+
+        Ok(Some((workinput, depth, priority)))
+    }
+
+    async fn remove(&mut self) {
+        if self.writer.take().is_some() {
+            self.reader = None;
+            let _ = async_std::fs::remove_file(&self.path).await;
+        }
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        if self.writer.take().is_some() {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Queue of work waiting for workers, ordered by priority and crawl strategy.
+/// Urls that were already queued or visited are dropped before they are queued.
+pub(crate) struct Frontier {
+    strategy: Strategy,
+    visited_links: Arc<RwLock<HashSet<String>>>,
+    // urls that were ever queued, so that url linked from many pages is queued once
+    seen: RwLock<HashSet<String>>,
+    counter: Arc<AtomicUsize>,
+    stats: Arc<Stats>,
+    heap: Mutex<BinaryHeap<Entry>>,
+    seq: AtomicU64,
+    capacity: Option<usize>,
+    spill: Option<AsyncMutex<Spill>>,
+    spilled: AtomicUsize,
+    // one token per queued entry, lets workers wait for work
+    tokens: Channels<()>,
+    // one slot per entry kept in memory, bounded by capacity
+    slots: Option<Channels<()>>,
+}
+
+impl fmt::Debug for Frontier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Frontier")
+            .field("strategy", &self.strategy)
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+impl Frontier {
+    pub(crate) fn new(
+        opts: &Opts,
+        visited_links: Arc<RwLock<HashSet<String>>>,
+        counter: Arc<AtomicUsize>,
+        stats: Arc<Stats>,
+    ) -> Self {
+        let spill = match (opts.queue_capacity, &opts.queue_spill_path) {
+            (Some(_), Some(path)) => Some(AsyncMutex::new(Spill::new(PathBuf::from(path)))),
+            _ => None,
+        };
+        let slots = match (opts.queue_capacity, &spill) {
+            (Some(capacity), None) => {
+                let (tx, rx) = bounded(capacity.max(1));
+                Some(Channels { tx, rx })
+            }
+            _ => None,
+        };
+
+        Frontier {
+            strategy: opts.strategy.clone(),
+            visited_links,
+            seen: RwLock::new(HashSet::new()),
+            counter,
+            stats,
+            heap: Mutex::new(BinaryHeap::new()),
+            seq: AtomicU64::new(0),
+            capacity: opts.queue_capacity,
+            spill,
+            spilled: AtomicUsize::new(0),
+            tokens: Channels::new(),
+            slots,
+        }
+    }
+
+    /// Queue work unless its url was already queued or visited,
+    /// waits for free space or spills to disk once queue is full
+    pub(crate) async fn push(
+        &self,
+        workinput: WorkInput,
+        depth: usize,
+        priority: i32,
+    ) -> Result<()> {
+        let duplicate = match &workinput {
+            WorkInput::Navigate(url, _)
+            | WorkInput::Download { url, .. }
+            | WorkInput::Sitemap(url) => {
+                // check and insert under one lock so that concurrent pushes queue url once
+                let mut seen = self.seen.write().await;
+                !seen.insert(url.clone()) || self.visited_links.read().await.contains(url)
+            }
+            _ => false,
+        };
+
+        if duplicate {
+            debug!("Skipping already queued {}", workinput.url());
+            self.stats.record_duplicate();
+            return Ok(());
+        }
+
+        debug!("Increasing counter by 1");
+        self.counter.fetch_add(1, AtomicOrdering::SeqCst);
+
+        match (&self.spill, self.capacity) {
+            (Some(spill), Some(capacity)) if self.in_memory() >= capacity => {
+                spill
+                    .lock()
+                    .await
+                    .write(&workinput, depth, priority)
+                    .await?;
+                self.spilled.fetch_add(1, AtomicOrdering::SeqCst);
+            }
+            _ => {
+                if let Some(slots) = &self.slots {
+                    slots.tx.send(()).await?;
+                }
+                self.insert(Job::new(workinput, depth), priority);
+            }
+        }
+
+        self.tokens.tx.send(()).await?;
+
+        Ok(())
+    }
+
+    /// Queue exit message for one of workers, it bypasses capacity and is taken first
+    pub(crate) async fn exit(&self) -> Result<()> {
+        self.insert(Job::exit(), i32::MAX);
+        self.tokens.tx.send(()).await?;
+
+        Ok(())
+    }
+
+    fn insert(&self, job: Job<WorkInput>, priority: i32) {
+        let seq = self.seq.fetch_add(1, AtomicOrdering::SeqCst);
+        let entry = Entry {
+            priority,
+            score: self.strategy.score(job.payload.url(), job.depth),
+            tiebreak: self.strategy.tiebreak(seq),
+            job,
+        };
+
+        self.heap.lock().unwrap().push(entry);
+    }
+
+    /// Wait for the most important queued job
+    pub(crate) async fn pop(&self) -> Result<Job<WorkInput>> {
+        self.tokens.rx.recv().await?;
+        self.take().await
+    }
+
+    async fn take(&self) -> Result<Job<WorkInput>> {
+        if let (Some(spill), Some(capacity)) = (&self.spill, self.capacity) {
+            let mut spill = spill.lock().await;
+            while self.in_memory() < capacity.max(1)
+                && self.spilled.load(AtomicOrdering::SeqCst) > 0
+            {
+                self.spilled.fetch_sub(1, AtomicOrdering::SeqCst);
+                match spill.read().await? {
+                    Some((workinput, depth, priority)) => {
+                        self.insert(Job::new(workinput, depth), priority)
+                    }
+                    None => break,
+                }
+            }
+        }
+
+        let job = self.heap.lock().unwrap().pop().map(|entry| entry.job);
+        if let (Some(slots), Some(job)) = (&self.slots, &job) {
+            // exit jobs bypass capacity and never take a slot
+            if !matches!(job.payload, WorkInput::Exit) {
+                let _ = slots.rx.try_recv();
+            }
+        }
+
+        job.ok_or(CrablerError::AsyncRecvError(RecvError))
+    }
+
+    /// Drop all queued work, used once crawl is stopped
+    pub(crate) async fn clear(&self) {
+        while self.tokens.rx.try_recv().is_ok() {
+            if self.take().await.is_ok() {
+                self.counter.fetch_sub(1, AtomicOrdering::SeqCst);
+            }
+        }
+    }
+
+    fn in_memory(&self) -> usize {
+        self.heap.lock().unwrap().len()
+    }
+
+    /// Number of queued jobs, including spilled ones
+    pub(crate) fn len(&self) -> usize {
+        self.in_memory() + self.spilled.load(AtomicOrdering::SeqCst)
+    }
+
+    pub(crate) async fn close(&self) {
+        self.tokens.tx.close();
+        if let Some(slots) = &self.slots {
+            slots.tx.close();
+        }
+        if let Some(spill) = &self.spill {
+            spill.lock().await.remove().await;
+        }
+    }
+}
@@ -39,31 +39,85 @@ pub use opts::*;
 mod errors;
 pub use errors::*;
 
-use async_std::channel::{unbounded, Receiver, RecvError, Sender};
+pub mod extract;
+pub use extract::Extract;
+
+mod feed;
+pub use feed::FeedItem;
+
+mod fetcher;
+pub use fetcher::{Body, FetchRequest, FetchResponse, Fetcher, MemoryFetcher, SurfFetcher};
+
+mod frontier;
+use frontier::Frontier;
+pub use frontier::{ScoreFn, Strategy};
+
+#[cfg(feature = "metrics")]
+mod metrics;
+
+mod middleware;
+pub use middleware::{HeaderMiddleware, Middleware, Middlewares, RequestAction, ResponseAction};
+
+mod navigation;
+pub use navigation::Navigation;
+
+mod output;
+#[cfg(feature = "sqlite")]
+pub use output::SqliteSink;
+pub use output::{CsvSink, JsonLinesSink, Output};
+
+mod page;
+pub use page::{FromPage, Page};
+
+mod pipeline;
+pub use pipeline::{Item, ItemProcessor, ItemSink, Pipeline};
+
+mod shutdown;
+pub use shutdown::CancellationHandle;
+
+mod sitemap;
+use sitemap::Sitemap;
+pub use sitemap::SitemapEntry;
+
+mod stats;
+use stats::Stats;
+pub use stats::{CrawlStats, FinishReason, LatencyHistogram, LATENCY_BUCKETS};
+
+mod structured_data;
+pub use structured_data::{MicrodataItem, MicrodataValue, StructuredData};
+
+mod url_pattern;
+pub use url_pattern::{url_matches, UrlPattern};
+
+mod warc;
+pub use warc::WarcReplay;
+use warc::WarcWriter;
+
+use async_std::channel::{unbounded, Receiver, Sender};
 use async_std::fs::File;
 use async_std::prelude::*;
 use async_std::sync::RwLock;
 pub use crabquery::{Document, Element};
-use log::{debug, error, info, warn};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
 use std::collections::HashSet;
 use std::fmt::Debug;
+use std::panic::AssertUnwindSafe;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::field::Empty;
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
 
 pub use async_trait::async_trait;
-pub use crabler_derive::WebScraper;
-
-#[cfg(feature = "debug")]
-fn enable_logging() {
-    femme::with_level(femme::LevelFilter::Info);
-}
-
-#[cfg(not(feature = "debug"))]
-fn enable_logging() {}
+pub use chrono::{DateTime, Utc};
+pub use crabler_derive::{Extract, WebScraper};
 
 #[async_trait(?Send)]
 pub trait WebScraper {
-    async fn dispatch_on_page(&mut self, page: String) -> Result<()>;
+    async fn dispatch_on_page(&mut self, page: Page) -> Result<()>;
     async fn dispatch_on_html(
         &mut self,
         selector: &str,
@@ -71,62 +125,216 @@ pub trait WebScraper {
         element: Element,
     ) -> Result<()>;
     async fn dispatch_on_response(&mut self, response: Response) -> Result<()>;
+    async fn dispatch_on_sitemap_entry(&mut self, entry: &SitemapEntry) -> Result<bool>;
+    async fn dispatch_on_feed_item(&mut self, response: Response, item: FeedItem) -> Result<()>;
+    async fn dispatch_on_structured_data(
+        &mut self,
+        response: Response,
+        document: &Document,
+    ) -> Result<()>;
+    async fn dispatch_on_error(&mut self, context: ErrorContext, error: CrablerError)
+        -> Result<()>;
+    async fn dispatch_on_start(&mut self, response: Response) -> Result<()>;
+    async fn dispatch_on_finish(&mut self, stats: &CrawlStats) -> Result<()>;
     fn all_html_selectors(&self) -> Vec<&str>;
     async fn run(self, opts: Opts) -> Result<()>;
+    async fn crawl(self, opts: Opts) -> Result<Self>
+    where
+        Self: Sized;
+    async fn run_with_stats(self, opts: Opts) -> Result<CrawlStats>;
 }
 
 #[derive(Debug)]
 enum WorkInput {
-    Navigate(String),
+    Navigate(String, Navigation),
     Download { url: String, destination: String },
+    Sitemap(String),
+    DiscoverSitemaps(String),
     Exit,
 }
 
+impl WorkInput {
+    fn url(&self) -> &str {
+        match self {
+            Self::Navigate(url, _) => url,
+            Self::Download { url, .. } => url,
+            Self::Sitemap(url) => url,
+            Self::DiscoverSitemaps(url) => url,
+            Self::Exit => "",
+        }
+    }
+
+    fn navigation(&self) -> Navigation {
+        match self {
+            Self::Navigate(_, navigation) => navigation.clone(),
+            _ => Navigation::default(),
+        }
+    }
+}
+
+/// Message passed between crabler and workers together with its crawl depth
+/// and the tracing span covering whole lifetime of the originating `WorkInput`
 #[derive(Debug)]
+struct Job<T> {
+    payload: T,
+    depth: usize,
+    span: Span,
+}
+
+/// Output of a worker together with navigation context of the `WorkInput` it was produced from
+type WorkResult = (WorkOutput, Navigation);
+
+impl Job<WorkInput> {
+    fn new(payload: WorkInput, depth: usize) -> Self {
+        let span = info_span!(
+            "work",
+            url = payload.url(),
+            depth,
+            worker = Empty,
+            // inputs are not retried, so every one is attempted once
+            attempt = 1,
+            status = Empty,
+        );
+
+        Job {
+            payload,
+            depth,
+            span,
+        }
+    }
+
+    fn exit() -> Self {
+        Job {
+            payload: WorkInput::Exit,
+            depth: 0,
+            span: Span::none(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct Response {
     pub url: String,
     pub status: u16,
     pub download_destination: Option<String>,
-    workinput_tx: Sender<WorkInput>,
-    counter: Arc<AtomicUsize>,
+    /// Number of navigations between seed url and this response
+    pub depth: usize,
+    /// User data attached to the navigation request of this page
+    pub meta: Value,
+    /// Handler named in the navigation request of this page, it is informational only,
+    /// all handlers still run and can check it to decide whether the page is theirs
+    pub handler: Option<String>,
+    // depth of requests queued from this response
+    child_depth: usize,
+    frontier: Arc<Frontier>,
+    items_tx: Sender<Item>,
+    stats: Arc<Stats>,
+    cancellation: CancellationHandle,
 }
 
 impl Response {
+    #[allow(clippy::too_many_arguments)]
     fn new(
         status: u16,
         url: String,
         download_destination: Option<String>,
-        workinput_tx: Sender<WorkInput>,
-        counter: Arc<AtomicUsize>,
+        depth: usize,
+        navigation: Navigation,
+        frontier: Arc<Frontier>,
+        items_tx: Sender<Item>,
+        stats: Arc<Stats>,
+        cancellation: CancellationHandle,
     ) -> Self {
         Response {
             status,
             url,
             download_destination,
-            workinput_tx,
-            counter,
+            depth,
+            meta: navigation.meta,
+            handler: navigation.handler,
+            child_depth: depth + 1,
+            frontier,
+            items_tx,
+            stats,
+            cancellation,
         }
     }
 
+    /// User data attached to the navigation request, deserialized into given type
+    pub fn meta_as<M: DeserializeOwned>(&self) -> Result<M> {
+        Ok(serde_json::from_value(self.meta.clone())?)
+    }
+
+    /// Snapshot of crawl counters at this point of the crawl
+    pub fn stats(&self) -> CrawlStats {
+        self.stats.snapshot()
+    }
+
+    /// Number of requests waiting in the queue, including spilled ones
+    pub fn queue_len(&self) -> usize {
+        self.frontier.len()
+    }
+
+    /// Send item to the item pipeline,
+    /// it will be processed after current handler returns
+    pub async fn emit<I: Serialize>(&mut self, item: &I) -> Result<()> {
+        let item = serde_json::to_value(item)?;
+        self.items_tx.send(item).await?;
+
+        Ok(())
+    }
+
     /// Schedule scraper to visit given url,
     /// this will be executed on one of worker tasks
     pub async fn navigate(&mut self, url: String) -> Result<()> {
-        debug!("Increasing counter by 1");
-        self.counter.fetch_add(1, Ordering::SeqCst);
-        self.workinput_tx.send(WorkInput::Navigate(url)).await?;
+        self.navigate_with_priority(url, 0).await
+    }
 
-        Ok(())
+    /// Schedule scraper to visit given url before any url with lower priority,
+    /// urls with the same priority are ordered by crawl `Strategy`
+    pub async fn navigate_with_priority(&mut self, url: String, priority: i32) -> Result<()> {
+        self.push_navigation(url, Navigation::default(), priority)
+            .await
+    }
+
+    /// Schedule scraper to visit given url with user data and target handler,
+    /// both are available on the response of visited page
+    pub async fn navigate_with(&mut self, url: String, navigation: Navigation) -> Result<()> {
+        self.push_navigation(url, navigation, 0).await
+    }
+
+    async fn push_navigation(
+        &mut self,
+        url: String,
+        navigation: Navigation,
+        priority: i32,
+    ) -> Result<()> {
+        if self.cancellation.is_cancelled() {
+            return Ok(());
+        }
+
+        self.frontier
+            .push(
+                WorkInput::Navigate(url, navigation),
+                self.child_depth,
+                priority,
+            )
+            .await
     }
 
     /// Schedule scraper to download file from url into destination path
     pub async fn download_file(&mut self, url: String, destination: String) -> Result<()> {
-        debug!("Increasing counter by 1");
-        self.counter.fetch_add(1, Ordering::SeqCst);
-        self.workinput_tx
-            .send(WorkInput::Download { url, destination })
-            .await?;
+        if self.cancellation.is_cancelled() {
+            return Ok(());
+        }
 
-        Ok(())
+        self.frontier
+            .push(
+                WorkInput::Download { url, destination },
+                self.child_depth,
+                0,
+            )
+            .await
     }
 }
 
@@ -144,17 +352,35 @@ impl<T> Channels<T> {
     }
 }
 
-pub struct Crabler<T>
+pub struct Crabler<T, F = SurfFetcher>
 where
     T: WebScraper,
+    F: Fetcher,
 {
     visited_links: Arc<RwLock<HashSet<String>>>,
-    workinput_ch: Channels<WorkInput>,
-    workoutput_ch: Channels<WorkOutput>,
+    frontier: Arc<Frontier>,
+    workoutput_ch: Channels<Job<WorkResult>>,
+    items_ch: Channels<Item>,
     scraper: T,
     counter: Arc<AtomicUsize>,
     workers: Vec<async_std::task::JoinHandle<()>>,
-    surf_client: surf::Client,
+    fetcher: Arc<F>,
+    warc: Option<Arc<WarcWriter>>,
+    middlewares: Middlewares,
+    sitemap_modified_since: Option<DateTime<Utc>>,
+    follow_feed_items: bool,
+    pipeline: Pipeline,
+    stats: Arc<Stats>,
+    cancellation: CancellationHandle,
+    shutdown_on_ctrl_c: bool,
+    shutdown_grace_period: Duration,
+    limits: Limits,
+    error_policy: ErrorPolicy,
+    signal_listener: Option<async_std::task::JoinHandle<()>>,
+    #[cfg(feature = "metrics")]
+    metrics_address: Option<String>,
+    #[cfg(feature = "metrics")]
+    metrics_server: Option<async_std::task::JoinHandle<()>>,
 }
 
 impl<T> Crabler<T>
@@ -163,157 +389,726 @@ where
 {
     /// Create new WebScraper out of given scraper struct
     pub fn new(scraper: T, opts: &Opts) -> Self {
+        Self::with_fetcher(scraper, opts, SurfFetcher::new(opts.follow_redirects))
+    }
+}
+
+impl<T> Crabler<T, Box<dyn Fetcher>>
+where
+    T: WebScraper,
+{
+    /// Create new WebScraper with fetcher picked by options,
+    /// responses are replayed from WARC archive when `Opts::with_warc_replay` is set
+    pub fn from_opts(scraper: T, opts: &Opts) -> Result<Self> {
+        let fetcher: Box<dyn Fetcher> = match &opts.warc_replay {
+            Some(path) => Box::new(WarcReplay::open(path)?),
+            None => Box::new(SurfFetcher::new(opts.follow_redirects)),
+        };
+
+        Ok(Self::with_fetcher(scraper, opts, fetcher))
+    }
+}
+
+impl<T, F> Crabler<T, F>
+where
+    T: WebScraper,
+    F: Fetcher,
+{
+    /// Create new WebScraper that fetches pages through given fetcher
+    pub fn with_fetcher(scraper: T, opts: &Opts, fetcher: F) -> Self {
         let visited_links = Arc::new(RwLock::new(HashSet::new()));
-        let workinput_ch = Channels::new();
         let workoutput_ch = Channels::new();
+        let items_ch = Channels::new();
         let counter = Arc::new(AtomicUsize::new(0));
         let workers = vec![];
-        let surf_client = if opts.follow_redirects {
-            surf::client().with(surf::middleware::Redirect::default())
-        } else {
-            surf::client()
-        };
+        let fetcher = Arc::new(fetcher);
+        let warc = opts
+            .warc_output
+            .as_ref()
+            .map(|path| Arc::new(WarcWriter::new(path)));
+        let sitemap_modified_since = opts.sitemap_modified_since;
+        let follow_feed_items = opts.follow_feed_items;
+        let pipeline = opts.pipeline.clone();
+        let stats = Arc::new(Stats::new());
+        let frontier = Arc::new(Frontier::new(
+            opts,
+            visited_links.clone(),
+            counter.clone(),
+            stats.clone(),
+        ));
+        let cancellation = opts.cancellation.clone();
+        let shutdown_on_ctrl_c = opts.shutdown_on_ctrl_c;
+        let shutdown_grace_period = opts.shutdown_grace_period;
+        let limits = opts.limits.clone();
 
         Crabler {
             visited_links,
-            workinput_ch,
+            frontier,
             workoutput_ch,
+            items_ch,
             scraper,
             counter,
             workers,
-            surf_client,
+            fetcher,
+            warc,
+            middlewares: opts.middlewares.clone(),
+            sitemap_modified_since,
+            follow_feed_items,
+            pipeline,
+            stats,
+            cancellation,
+            shutdown_on_ctrl_c,
+            shutdown_grace_period,
+            limits,
+            error_policy: opts.error_policy,
+            signal_listener: None,
+            #[cfg(feature = "metrics")]
+            metrics_address: opts.metrics_address.clone(),
+            #[cfg(feature = "metrics")]
+            metrics_server: None,
         }
     }
 
     async fn shutdown(&mut self) -> Result<()> {
+        if self.cancellation.is_cancelled() {
+            // abort requests that did not finish within grace period
+            for worker in self.workers.drain(..) {
+                worker.cancel().await;
+            }
+        }
+
+        if let Some(listener) = self.signal_listener.take() {
+            listener.cancel().await;
+        }
+
         for _ in self.workers.iter() {
-            self.workinput_ch.tx.send(WorkInput::Exit).await?;
+            self.frontier.exit().await?;
         }
 
-        self.workinput_ch.tx.close();
-        self.workinput_ch.rx.close();
+        self.frontier.close().await;
         self.workoutput_ch.tx.close();
         self.workoutput_ch.rx.close();
 
+        self.process_items().await?;
+        let flushed = self.pipeline.flush().await;
+        self.apply_error_policy(flushed, "", "pipeline", None)
+            .await?;
+        self.items_ch.tx.close();
+        self.items_ch.rx.close();
+
+        #[cfg(feature = "metrics")]
+        if let Some(server) = self.metrics_server.take() {
+            server.cancel().await;
+        }
+
+        Ok(())
+    }
+
+    fn response(
+        &self,
+        status: u16,
+        url: String,
+        download_destination: Option<String>,
+        depth: usize,
+        navigation: &Navigation,
+    ) -> Response {
+        Response::new(
+            status,
+            url,
+            download_destination,
+            depth,
+            navigation.clone(),
+            self.frontier.clone(),
+            self.items_ch.tx.clone(),
+            self.stats.clone(),
+            self.cancellation.clone(),
+        )
+    }
+
+    /// Handle that stops this crawl when cancelled
+    pub fn cancellation_handle(&self) -> CancellationHandle {
+        self.cancellation.clone()
+    }
+
+    fn listen_for_ctrl_c(&mut self) -> Result<()> {
+        let mut signals = async_signal::Signals::new([async_signal::Signal::Int])?;
+        let cancellation = self.cancellation.clone();
+
+        self.signal_listener = Some(async_std::task::spawn(async move {
+            if signals.next().await.is_some() {
+                info!("Received Ctrl-C, shutting down");
+                cancellation.cancel();
+            }
+        }));
+
+        Ok(())
+    }
+
+    /// Snapshot of crawl counters, can be taken while crawl is running
+    pub fn stats(&self) -> CrawlStats {
+        self.stats.snapshot()
+    }
+
+    #[cfg(feature = "metrics")]
+    async fn start_metrics_server(&mut self) -> Result<()> {
+        let address = match &self.metrics_address {
+            Some(address) => address,
+            None => return Ok(()),
+        };
+
+        let listener = async_std::net::TcpListener::bind(address).await?;
+        info!("Serving metrics on http://{}/metrics", address);
+
+        let metrics = metrics::Metrics {
+            stats: self.stats.clone(),
+            counter: self.counter.clone(),
+            workers: self.workers.len(),
+            frontier: self.frontier.clone(),
+            workoutput_rx: self.workoutput_ch.rx.clone(),
+        };
+        self.metrics_server = Some(async_std::task::spawn(metrics.serve(listener)));
+
+        Ok(())
+    }
+
+    async fn process_items(&mut self) -> Result<()> {
+        while let Ok(item) = self.items_ch.rx.try_recv() {
+            let result = self.pipeline.process(item).await;
+            self.apply_error_policy(result, "", "pipeline", None)
+                .await?;
+        }
+
         Ok(())
     }
 
     /// Schedule scraper to visit given url,
     /// this will be executed on one of worker tasks
     pub async fn navigate(&mut self, url: &str) -> Result<()> {
-        debug!("Increasing counter by 1");
-        self.counter.fetch_add(1, Ordering::SeqCst);
-        Ok(self
-            .workinput_ch
-            .tx
-            .send(WorkInput::Navigate(url.to_string()))
-            .await?)
+        self.enqueue(
+            WorkInput::Navigate(url.to_string(), Navigation::default()),
+            0,
+        )
+        .await
+    }
+
+    /// Schedule scraper to fetch given sitemap or sitemap index,
+    /// urls found in it will be visited
+    pub async fn sitemap(&mut self, url: &str) -> Result<()> {
+        self.enqueue(WorkInput::Sitemap(url.to_string()), 0).await
+    }
+
+    /// Schedule scraper to look up sitemaps for the host of given url,
+    /// robots.txt is checked first, well known locations are used as a fallback
+    pub async fn discover_sitemaps(&mut self, url: &str) -> Result<()> {
+        self.enqueue(WorkInput::DiscoverSitemaps(url.to_string()), 0)
+            .await
     }
 
-    /// Run processing loop for the given WebScraper
-    pub async fn run(&mut self) -> Result<()> {
-        enable_logging();
+    async fn enqueue(&mut self, workinput: WorkInput, depth: usize) -> Result<()> {
+        if self.cancellation.is_cancelled() {
+            return Ok(());
+        }
+
+        self.frontier.push(workinput, depth, 0).await
+    }
+
+    fn is_fresh(&self, entry: &SitemapEntry) -> bool {
+        match &self.sitemap_modified_since {
+            Some(since) => entry.modified_since(since),
+            None => true,
+        }
+    }
+
+    /// Queue seed urls and sitemaps from given options, start worker tasks
+    /// and run processing loop until all work is done
+    pub async fn start(&mut self, opts: &Opts) -> Result<CrawlStats> {
+        self.stats.start_clock();
+
+        // workers go first so that seeds can't block on bounded queue
+        for _ in 0..opts.threads {
+            self.start_worker();
+        }
+
+        if let Err(e) = self.on_start().await {
+            self.shutdown().await?;
+            return Err(e);
+        }
+
+        for url in &opts.urls {
+            self.navigate(url).await?;
+        }
+
+        for url in &opts.sitemaps {
+            self.sitemap(url).await?;
+        }
+
+        if opts.discover_sitemaps {
+            for url in &opts.urls {
+                self.discover_sitemaps(url).await?;
+            }
+        }
+
+        self.run().await
+    }
+
+    /// Run processing loop for the given WebScraper,
+    /// returns crawl counters once all work is done
+    pub async fn run(&mut self) -> Result<CrawlStats> {
+        self.stats.start_clock();
+
+        #[cfg(feature = "metrics")]
+        self.start_metrics_server().await?;
+
+        if self.shutdown_on_ctrl_c {
+            self.listen_for_ctrl_c()?;
+        }
 
         let ret = self.event_loop().await;
-        self.shutdown().await?;
-        ret
+        if let Err(e) = &ret {
+            self.stats.record_error(e.kind());
+        }
+        if self.cancellation.is_cancelled() {
+            self.stats.finish(FinishReason::Cancelled);
+        }
+
+        let shutdown = self.shutdown().await;
+
+        // teardown runs even when crawl failed, crawl error is reported first
+        let stats = self.stats();
+        let result = catch_panic(
+            "",
+            self.scraper
+                .dispatch_on_finish(&stats)
+                .instrument(info_span!("dispatch", handler = "on_finish")),
+        )
+        .await;
+        let finish = self.apply_error_policy(result, "", "on_finish", None).await;
+
+        ret?;
+        shutdown?;
+        finish?;
+
+        Ok(self.stats())
+    }
+
+    /// Run setup handler, requests it queues are treated as seeds
+    async fn on_start(&mut self) -> Result<()> {
+        let mut response = self.response(0, String::new(), None, 0, &Navigation::default());
+        response.child_depth = 0;
+        let result = catch_panic(
+            "",
+            self.scraper
+                .dispatch_on_start(response)
+                .instrument(info_span!("dispatch", handler = "on_start")),
+        )
+        .await;
+
+        self.apply_error_policy(result, "", "on_start", None).await
     }
 
     async fn event_loop(&mut self) -> Result<()> {
+        let mut deadline = None;
+
         loop {
-            let output = self.workoutput_ch.rx.recv().await?;
-            let response_url;
-            let response_status;
-            let mut response_destination = None;
-
-            match output {
-                WorkOutput::Markup { text, url, status } => {
-                    info!("Fetched markup from: {}", url);
-                    self.scraper.dispatch_on_page(text.clone()).await?;
-                    let document = Document::from(text);
-                    response_url = url.clone();
-                    response_status = status;
-
-                    let selectors = self
-                        .scraper
-                        .all_html_selectors()
-                        .iter()
-                        .map(|s| s.to_string())
-                        .collect::<Vec<_>>();
-
-                    for selector in selectors {
-                        for el in document.select(selector.as_str()) {
-                            let response = Response::new(
-                                status,
-                                url.clone(),
-                                None,
-                                self.workinput_ch.tx.clone(),
-                                self.counter.clone(),
-                            );
-                            self.scraper
-                                .dispatch_on_html(selector.as_str(), response, el)
-                                .await?;
+            let Job {
+                payload: (payload, navigation),
+                depth,
+                span,
+            } = match self.next_output(&mut deadline).await? {
+                Some(job) => job,
+                None => return Ok(()),
+            };
+
+            self.process_output(payload, depth, &navigation)
+                .instrument(span)
+                .await?;
+            self.check_limits();
+
+            debug!("Decreasing counter by 1");
+            self.counter.fetch_sub(1, Ordering::SeqCst);
+
+            let cur_count = self.counter.load(Ordering::SeqCst);
+            debug!("Done processing work output, counter is at {}", cur_count);
+            debug!("Queue len: {}", self.workoutput_ch.rx.len());
+
+            if cur_count == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Stop crawl once any of configured limits is reached
+    fn check_limits(&self) {
+        if self.cancellation.is_cancelled() {
+            return;
+        }
+
+        let stats = self.stats.snapshot();
+        let limits = &self.limits;
+
+        if matches!(limits.pages, Some(max) if stats.pages() >= max) {
+            self.stop(FinishReason::PageLimit);
+        } else if matches!(limits.bytes, Some(max) if stats.bytes >= max) {
+            self.stop(FinishReason::ByteLimit);
+        } else if matches!(limits.errors, Some(max) if stats.failures() >= max) {
+            self.stop(FinishReason::ErrorLimit);
+        } else if matches!(limits.duration, Some(max) if stats.elapsed >= max) {
+            self.stop(FinishReason::TimeLimit);
+        }
+    }
+
+    fn stop(&self, reason: FinishReason) {
+        info!("Stopping crawl: {:?}", reason);
+        self.stats.finish(reason);
+        self.cancellation.cancel();
+    }
+
+    /// Wait for next work output, once crawl is cancelled queued work is dropped
+    /// and outputs are awaited only until grace period deadline
+    async fn next_output(
+        &mut self,
+        deadline: &mut Option<Instant>,
+    ) -> Result<Option<Job<WorkResult>>> {
+        if deadline.is_none() {
+            if !self.cancellation.is_cancelled() {
+                let output = async { Some(self.workoutput_ch.rx.recv().await) };
+                let cancelled = async {
+                    self.cancellation.cancelled().await;
+                    None
+                };
+                let time_limit = async {
+                    match self.limits.duration {
+                        Some(duration) => {
+                            let elapsed = self.stats.elapsed();
+                            async_std::task::sleep(duration.saturating_sub(elapsed)).await
                         }
+                        None => async_std::future::pending().await,
                     }
+                    None
+                };
+
+                if let Some(output) = output.race(cancelled).race(time_limit).await {
+                    return Ok(Some(output?));
                 }
-                WorkOutput::Download { url, destination } => {
-                    debug!("Downloaded: {} -> {}", url, destination);
-                    response_url = url;
-                    response_destination = Some(destination);
-                    response_status = 200;
+
+                if !self.cancellation.is_cancelled() {
+                    self.stop(FinishReason::TimeLimit);
+                }
+            }
+
+            info!("Crawl stopped, waiting for requests in flight");
+            *deadline = Some(Instant::now() + self.shutdown_grace_period);
+
+            self.frontier.clear().await;
+        }
+
+        if self.counter.load(Ordering::SeqCst) == 0 {
+            return Ok(None);
+        }
+
+        let remaining = deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+            .unwrap_or_default();
+
+        match async_std::future::timeout(remaining, self.workoutput_ch.rx.recv()).await {
+            Ok(output) => Ok(Some(output?)),
+            Err(_) => {
+                warn!("Grace period is over, aborting requests in flight");
+                Ok(None)
+            }
+        }
+    }
+
+    async fn process_output(
+        &mut self,
+        output: WorkOutput,
+        depth: usize,
+        navigation: &Navigation,
+    ) -> Result<()> {
+        let response_url;
+        let response_status;
+        let mut response_destination = None;
+
+        match output {
+            WorkOutput::Markup {
+                text,
+                url,
+                status,
+                content_type,
+                headers,
+            } if feed::is_feed(content_type.as_deref(), &text) => {
+                info!("Fetched feed from: {}", url);
+                let document =
+                    info_span!("parse").in_scope(|| Rc::new(Document::from(text.as_str())));
+                let response = self.response(status, url.clone(), None, depth, navigation);
+                let page = Page::new(response, headers, text.clone(), document);
+                let result = catch_panic(
+                    &url,
+                    self.scraper
+                        .dispatch_on_page(page)
+                        .instrument(info_span!("dispatch", handler = "on_page")),
+                )
+                .await;
+                self.apply_error_policy(result, &url, "on_page", None)
+                    .await?;
+                response_url = url.clone();
+                response_status = status;
+
+                // malformed feed goes through error policy like a failed handler
+                let items = info_span!("parse").in_scope(|| feed::parse_feed(&text));
+                let items = self
+                    .apply_error_policy(items, &url, "on_feed_item", None)
+                    .await?;
+                for item in items {
+                    let link = item.link.clone();
+                    let response = self.response(status, url.clone(), None, depth, navigation);
+                    let result = catch_panic(
+                        &url,
+                        self.scraper
+                            .dispatch_on_feed_item(response, item)
+                            .instrument(info_span!("dispatch", handler = "on_feed_item")),
+                    )
+                    .await;
+                    self.apply_error_policy(result, &url, "on_feed_item", None)
+                        .await?;
+
+                    if let (true, Some(link)) = (self.follow_feed_items, link) {
+                        self.enqueue(WorkInput::Navigate(link, Navigation::default()), depth + 1)
+                            .await?;
+                    }
                 }
-                WorkOutput::Noop(url) => {
-                    debug!("Noop: {}", url);
-                    response_url = url;
-                    response_status = 304;
+            }
+            WorkOutput::Markup {
+                text,
+                url,
+                status,
+                headers,
+                ..
+            } => {
+                info!("Fetched markup from: {}", url);
+                let document =
+                    info_span!("parse").in_scope(|| Rc::new(Document::from(text.as_str())));
+                let response = self.response(status, url.clone(), None, depth, navigation);
+                let page = Page::new(response, headers, text, document.clone());
+                let result = catch_panic(
+                    &url,
+                    self.scraper
+                        .dispatch_on_page(page)
+                        .instrument(info_span!("dispatch", handler = "on_page")),
+                )
+                .await;
+                self.apply_error_policy(result, &url, "on_page", None)
+                    .await?;
+                response_url = url.clone();
+                response_status = status;
+
+                let selectors = self
+                    .scraper
+                    .all_html_selectors()
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect::<Vec<_>>();
+
+                for selector in selectors {
+                    for el in document.select(selector.as_str()) {
+                        let response = self.response(status, url.clone(), None, depth, navigation);
+                        let result = catch_panic(
+                            &url,
+                            self.scraper
+                                .dispatch_on_html(selector.as_str(), response, el)
+                                .instrument(info_span!(
+                                    "dispatch",
+                                    handler = "on_html",
+                                    selector = selector.as_str()
+                                )),
+                        )
+                        .await;
+                        self.apply_error_policy(result, &url, "on_html", Some(&selector))
+                            .await?;
+                    }
                 }
-                WorkOutput::Error(url, e) => {
-                    error!("Error from {}: {}", url, e);
-                    response_url = url;
-                    response_status = 500;
+
+                let response = self.response(status, url.clone(), None, depth, navigation);
+                let result = catch_panic(
+                    &url,
+                    self.scraper
+                        .dispatch_on_structured_data(response, &document)
+                        .instrument(info_span!("dispatch", handler = "on_structured_data")),
+                )
+                .await;
+                self.apply_error_policy(result, &url, "on_structured_data", None)
+                    .await?;
+            }
+            WorkOutput::Sitemap {
+                url,
+                status,
+                sitemap,
+            } => {
+                info!("Fetched sitemap from: {}", url);
+                response_url = url;
+                response_status = status;
+
+                for entry in sitemap.sitemaps {
+                    if self.is_fresh(&entry) {
+                        self.enqueue(WorkInput::Sitemap(entry.loc), depth + 1)
+                            .await?;
+                    }
                 }
-                WorkOutput::Exit => {
-                    error!("Received exit output");
-                    response_url = "".to_string();
-                    response_status = 500;
+
+                for entry in sitemap.urls {
+                    if !self.is_fresh(&entry) {
+                        continue;
+                    }
+
+                    let result = catch_panic(
+                        &entry.loc,
+                        self.scraper
+                            .dispatch_on_sitemap_entry(&entry)
+                            .instrument(info_span!("dispatch", handler = "on_sitemap_entry")),
+                    )
+                    .await;
+                    if self
+                        .apply_error_policy(result, &entry.loc, "on_sitemap_entry", None)
+                        .await?
+                    {
+                        self.enqueue(
+                            WorkInput::Navigate(entry.loc, Navigation::default()),
+                            depth + 1,
+                        )
+                        .await?;
+                    }
                 }
             }
+            WorkOutput::SitemapLocations {
+                url,
+                status,
+                locations,
+            } => {
+                debug!("Sitemaps listed for {}: {:?}", url, locations);
+                response_url = url;
+                response_status = status;
 
-            let response = Response::new(
-                response_status,
-                response_url,
-                response_destination,
-                self.workinput_ch.tx.clone(),
-                self.counter.clone(),
-            );
-            self.scraper.dispatch_on_response(response).await?;
+                for location in locations {
+                    self.enqueue(WorkInput::Sitemap(location), depth + 1)
+                        .await?;
+                }
+            }
+            WorkOutput::Download { url, destination } => {
+                debug!("Downloaded: {} -> {}", url, destination);
+                response_url = url;
+                response_destination = Some(destination);
+                response_status = 200;
+            }
+            WorkOutput::Noop(url) => {
+                debug!("Noop: {}", url);
+                response_url = url;
+                response_status = 304;
+            }
+            WorkOutput::Error(url, e) => {
+                error!("Error from {}: {}", url, e);
+                self.stats.record_error(e.kind());
+                response_url = url;
+                response_status = 500;
+            }
+            WorkOutput::Exit => {
+                error!("Received exit output");
+                response_url = "".to_string();
+                response_status = 500;
+            }
+        }
 
-            debug!("Decreasing counter by 1");
-            self.counter.fetch_sub(1, Ordering::SeqCst);
+        let response = self.response(
+            response_status,
+            response_url.clone(),
+            response_destination,
+            depth,
+            navigation,
+        );
+        let result = catch_panic(
+            &response_url,
+            self.scraper
+                .dispatch_on_response(response)
+                .instrument(info_span!("dispatch", handler = "on_response")),
+        )
+        .await;
+        self.apply_error_policy(result, &response_url, "on_response", None)
+            .await?;
+        self.process_items().await
+    }
 
-            let cur_count = self.counter.load(Ordering::SeqCst);
-            debug!("Done processing work output, counter is at {}", cur_count);
-            debug!("Queue len: {}", self.workoutput_ch.rx.len());
+    /// Decide what happens with failed handler according to error policy,
+    /// returns error only if crawl has to be aborted
+    async fn apply_error_policy<R: Default>(
+        &mut self,
+        result: Result<R>,
+        url: &str,
+        hook: &'static str,
+        selector: Option<&str>,
+    ) -> Result<R> {
+        let e = match result {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+        let kind = e.kind();
 
-            if cur_count == 0 {
-                return Ok(());
+        match self.error_policy {
+            ErrorPolicy::Abort => return Err(e),
+            ErrorPolicy::LogAndContinue => error!("{} handler failed on {}: {}", hook, url, e),
+            ErrorPolicy::Handler => {
+                let context = ErrorContext {
+                    url: url.to_string(),
+                    hook,
+                    selector: selector.map(|selector| selector.to_string()),
+                };
+                catch_panic(
+                    url,
+                    self.scraper
+                        .dispatch_on_error(context, e)
+                        .instrument(info_span!("dispatch", handler = "on_error")),
+                )
+                .await?;
             }
         }
+        self.stats.record_error(kind);
+
+        Ok(R::default())
+    }
+
+    /// Scraper struct used by this crabler
+    pub fn scraper(&self) -> &T {
+        &self.scraper
+    }
+
+    /// Mutable access to scraper struct used by this crabler
+    pub fn scraper_mut(&mut self) -> &mut T {
+        &mut self.scraper
+    }
+
+    /// Consume crabler and return scraper struct with all the state it collected
+    pub fn into_scraper(self) -> T {
+        self.scraper
     }
 
     /// Create and start new worker tasks.
     /// Worker task will automatically exit after scraper instance is freed.
     pub fn start_worker(&mut self) {
         let visited_links = self.visited_links.clone();
-        let workinput_rx = self.workinput_ch.rx.clone();
+        let frontier = self.frontier.clone();
         let workoutput_tx = self.workoutput_ch.tx.clone();
-        let surf_client = self.surf_client.clone();
+        let fetcher = self.fetcher.clone();
+        let warc = self.warc.clone();
+        let middlewares = self.middlewares.clone();
+        let stats = self.stats.clone();
 
         let worker = Worker::new(
+            self.workers.len(),
             visited_links,
-            workinput_rx,
+            frontier,
             workoutput_tx,
-            surf_client,
+            fetcher,
+            warc,
+            middlewares,
+            stats,
         );
 
         let handle = async_std::task::spawn(async move {
@@ -334,25 +1129,60 @@ where
     }
 }
 
-struct Worker {
+/// Turn panic inside of scraper handler into `HandlerPanicked` error,
+/// scraper state is left as it was at the moment of panic
+async fn catch_panic<R>(url: &str, handler: impl Future<Output = Result<R>>) -> Result<R> {
+    match futures::FutureExt::catch_unwind(AssertUnwindSafe(handler)).await {
+        Ok(result) => result,
+        Err(payload) => {
+            let payload = if let Some(message) = payload.downcast_ref::<&str>() {
+                message.to_string()
+            } else if let Some(message) = payload.downcast_ref::<String>() {
+                message.clone()
+            } else {
+                "unknown panic payload".to_string()
+            };
+
+            Err(CrablerError::HandlerPanicked {
+                url: url.to_string(),
+                payload,
+            })
+        }
+    }
+}
+
+struct Worker<F: Fetcher> {
+    id: usize,
     visited_links: Arc<RwLock<HashSet<String>>>,
-    workinput_rx: Receiver<WorkInput>,
-    workoutput_tx: Sender<WorkOutput>,
-    surf_client: surf::Client,
+    frontier: Arc<Frontier>,
+    workoutput_tx: Sender<Job<WorkResult>>,
+    fetcher: Arc<F>,
+    warc: Option<Arc<WarcWriter>>,
+    middlewares: Middlewares,
+    stats: Arc<Stats>,
 }
 
-impl Worker {
+impl<F: Fetcher> Worker<F> {
+    #[allow(clippy::too_many_arguments)]
     fn new(
+        id: usize,
         visited_links: Arc<RwLock<HashSet<String>>>,
-        workinput_rx: Receiver<WorkInput>,
-        workoutput_tx: Sender<WorkOutput>,
-        surf_client: surf::Client,
+        frontier: Arc<Frontier>,
+        workoutput_tx: Sender<Job<WorkResult>>,
+        fetcher: Arc<F>,
+        warc: Option<Arc<WarcWriter>>,
+        middlewares: Middlewares,
+        stats: Arc<Stats>,
     ) -> Self {
         Worker {
+            id,
             visited_links,
-            workinput_rx,
+            frontier,
             workoutput_tx,
-            surf_client,
+            fetcher,
+            warc,
+            middlewares,
+            stats,
         }
     }
 
@@ -360,24 +1190,52 @@ impl Worker {
         let workoutput_tx = self.workoutput_tx.clone();
 
         loop {
-            let workinput = self.workinput_rx.recv().await;
-            if let Err(RecvError) = workinput {
-                continue;
-            }
+            let job = match self.frontier.pop().await {
+                Ok(job) => job,
+                Err(CrablerError::AsyncRecvError(_)) => continue,
+                Err(e) => {
+                    // queued entry could not be restored, report it so it is still accounted for
+                    let job = Job {
+                        payload: (WorkOutput::Error(String::new(), e), Navigation::default()),
+                        depth: 0,
+                        span: Span::none(),
+                    };
+                    workoutput_tx.send(job).await?;
+                    continue;
+                }
+            };
+            job.span.record("worker", self.id);
 
-            let workinput = workinput?;
-            let payload = self.process_message(workinput).await;
+            self.stats.worker_busy();
+            let navigation = job.payload.navigation();
+            let payload = self
+                .process_message(job.payload)
+                .instrument(job.span.clone())
+                .await;
+            self.stats.worker_idle();
 
             match payload {
                 Ok(WorkOutput::Exit) => return Ok(()),
-                _ => workoutput_tx.send(payload?).await?,
+                _ => {
+                    let payload = payload?;
+                    if let Some(status) = payload.status() {
+                        job.span.record("status", status);
+                    }
+
+                    let job = Job {
+                        payload: (payload, navigation),
+                        depth: job.depth,
+                        span: job.span,
+                    };
+                    workoutput_tx.send(job).await?
+                }
             }
         }
     }
 
     async fn process_message(&self, workinput: WorkInput) -> Result<WorkOutput> {
         match workinput {
-            WorkInput::Navigate(url) => {
+            WorkInput::Navigate(url, _) => {
                 let workoutput = self.navigate(url.clone()).await;
 
                 if let Err(e) = workoutput {
@@ -395,19 +1253,54 @@ impl Worker {
                     workoutput
                 }
             }
+            WorkInput::Sitemap(url) => {
+                let workoutput = self.sitemap(url.clone()).await;
+
+                if let Err(e) = workoutput {
+                    Ok(WorkOutput::Error(url, e))
+                } else {
+                    workoutput
+                }
+            }
+            WorkInput::DiscoverSitemaps(url) => {
+                let workoutput = self.discover_sitemaps(url.clone()).await;
+
+                if let Err(e) = workoutput {
+                    Ok(WorkOutput::Error(url, e))
+                } else {
+                    workoutput
+                }
+            }
             WorkInput::Exit => Ok(WorkOutput::Exit),
         }
     }
 
+    /// Mark url as visited, returns false if it was visited before.
+    /// Check and insert happen under one lock so that only one worker can claim url.
+    async fn visit(&self, url: &str) -> bool {
+        self.visited_links.write().await.insert(url.to_string())
+    }
+
     async fn navigate(&self, url: String) -> Result<WorkOutput> {
-        let contains = self.visited_links.read().await.contains(&url.clone());
+        if self.visit(&url).await {
+            let started = Instant::now();
+            let workoutput = async {
+                match self.fetch(&url).await? {
+                    Some(response) => WorkOutput::try_from_response(response, url.clone()).await,
+                    None => Ok(WorkOutput::Noop(url.clone())),
+                }
+            }
+            .instrument(info_span!("fetch"))
+            .await?;
 
-        if !contains {
-            self.visited_links.write().await.insert(url.clone());
-            let response = self.surf_client.get(&url).await?;
+            if let WorkOutput::Markup { status, text, .. } = &workoutput {
+                self.stats
+                    .record_response(&url, *status, text.len(), started.elapsed());
+            }
 
-            WorkOutput::try_from_response(response, url.clone()).await
+            Ok(workoutput)
         } else {
+            self.stats.record_duplicate();
             Ok(WorkOutput::Noop(url))
         }
     }
@@ -417,15 +1310,160 @@ impl Worker {
 
         if !contains {
             // need to notify parent about work being done
-            let response = self.surf_client.get(&*url).await?.body_bytes().await?;
-            let mut dest = File::create(destination.clone()).await?;
-            dest.write_all(&response).await?;
+            let started = Instant::now();
+            let (status, body) = match self
+                .fetch_bytes(&url)
+                .instrument(info_span!("fetch"))
+                .await?
+            {
+                Some(fetched) => fetched,
+                None => return Ok(WorkOutput::Noop(url)),
+            };
+            self.stats
+                .record_response(&url, status, body.len(), started.elapsed());
+
+            // write into temporary file first so that aborted download
+            // never leaves truncated file at destination
+            let partial = format!("{}.part", destination);
+            let mut dest = File::create(&partial).await?;
+            dest.write_all(&body).await?;
+            dest.flush().await?;
+            async_std::fs::rename(&partial, &destination).await?;
 
             Ok(WorkOutput::Download { url, destination })
         } else {
+            self.stats.record_duplicate();
+            Ok(WorkOutput::Noop(url))
+        }
+    }
+
+    async fn sitemap(&self, url: String) -> Result<WorkOutput> {
+        if self.visit(&url).await {
+            let started = Instant::now();
+            let (status, body) = match self
+                .fetch_bytes(&url)
+                .instrument(info_span!("fetch"))
+                .await?
+            {
+                Some(fetched) => fetched,
+                None => return Ok(WorkOutput::Noop(url)),
+            };
+            self.stats
+                .record_response(&url, status, body.len(), started.elapsed());
+
+            let sitemap = if (200..300).contains(&status) {
+                info_span!("parse").in_scope(|| sitemap::parse_sitemap(&body))?
+            } else {
+                warn!("Sitemap {} responded with {}", url, status);
+                Sitemap::default()
+            };
+
+            Ok(WorkOutput::Sitemap {
+                url,
+                status,
+                sitemap,
+            })
+        } else {
+            self.stats.record_duplicate();
             Ok(WorkOutput::Noop(url))
         }
     }
+
+    async fn discover_sitemaps(&self, url: String) -> Result<WorkOutput> {
+        let robots = sitemap::robots_url(&url)?;
+
+        if self.visit(&robots).await {
+            let (status, mut locations) = match self.robots_sitemaps(&robots).await {
+                Ok(fetched) => fetched,
+                Err(e) => {
+                    warn!("Failed to fetch {}: {}", robots, e);
+                    (500, vec![])
+                }
+            };
+
+            if locations.is_empty() {
+                locations = sitemap::well_known_sitemaps(&url)?;
+            }
+
+            Ok(WorkOutput::SitemapLocations {
+                url: robots,
+                status,
+                locations,
+            })
+        } else {
+            Ok(WorkOutput::Noop(robots))
+        }
+    }
+
+    /// Sitemaps listed in robots.txt together with its status,
+    /// dropped requests are reported as not modified
+    async fn robots_sitemaps(&self, url: &str) -> Result<(u16, Vec<String>)> {
+        let started = Instant::now();
+        let (status, body) = match self
+            .fetch_bytes(url)
+            .instrument(info_span!("fetch"))
+            .await?
+        {
+            Some(fetched) => fetched,
+            None => return Ok((304, vec![])),
+        };
+        self.stats
+            .record_response(url, status, body.len(), started.elapsed());
+
+        if (200..300).contains(&status) {
+            let body = String::from_utf8_lossy(&body);
+            let locations = info_span!("parse").in_scope(|| sitemap::parse_robots(&body));
+            Ok((status, locations))
+        } else {
+            Ok((status, vec![]))
+        }
+    }
+
+    /// Fetch url through middlewares,
+    /// returns None when one of middlewares dropped request or response.
+    /// Exchanges with fetcher are recorded when WARC output is enabled.
+    async fn fetch(&self, url: &str) -> Result<Option<FetchResponse>> {
+        let mut request = FetchRequest::get(url);
+
+        let (ran, action) = self.middlewares.before_request(&mut request).await?;
+        let mut response = match action {
+            RequestAction::Continue => {
+                let mut response = self.fetcher.fetch(request.clone()).await?;
+
+                if let Some(warc) = &self.warc {
+                    let body = response.bytes().await?;
+                    let records = warc.records(&request, &response, &body)?;
+                    warc.append(records).await?;
+                }
+
+                response
+            }
+            RequestAction::Respond(response) => response,
+            RequestAction::Drop => {
+                debug!("Request to {} dropped by middleware", url);
+                return Ok(None);
+            }
+        };
+
+        match self
+            .middlewares
+            .after_response(ran, &request, &mut response)
+            .await?
+        {
+            ResponseAction::Continue => Ok(Some(response)),
+            ResponseAction::Drop => {
+                debug!("Response from {} dropped by middleware", url);
+                Ok(None)
+            }
+        }
+    }
+
+    async fn fetch_bytes(&self, url: &str) -> Result<Option<(u16, Vec<u8>)>> {
+        match self.fetch(url).await? {
+            Some(mut response) => Ok(Some((response.status, response.take_bytes().await?))),
+            None => Ok(None),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -434,25 +1472,55 @@ enum WorkOutput {
         url: String,
         text: String,
         status: u16,
+        content_type: Option<String>,
+        headers: Vec<(String, String)>,
     },
     Download {
         url: String,
         destination: String,
     },
+    Sitemap {
+        url: String,
+        status: u16,
+        sitemap: Sitemap,
+    },
+    SitemapLocations {
+        url: String,
+        status: u16,
+        locations: Vec<String>,
+    },
     Noop(String),
     Error(String, CrablerError),
     Exit,
 }
 
 impl WorkOutput {
-    async fn try_from_response(mut response: surf::Response, url: String) -> Result<Self> {
-        let status = response.status().into();
-        let text = response.body_string().await?;
+    fn status(&self) -> Option<u16> {
+        match self {
+            Self::Markup { status, .. } => Some(*status),
+            Self::Sitemap { status, .. } | Self::SitemapLocations { status, .. } => Some(*status),
+            Self::Download { .. } => Some(200),
+            Self::Noop(_) => Some(304),
+            Self::Error(..) => Some(500),
+            Self::Exit => None,
+        }
+    }
+
+    async fn try_from_response(mut response: FetchResponse, url: String) -> Result<Self> {
+        let status = response.status;
+        let content_type = response.content_type();
+        let text = String::from_utf8_lossy(&response.take_bytes().await?).to_string();
 
         if text.is_empty() {
             error!("body is empty")
         }
 
-        Ok(WorkOutput::Markup { status, url, text })
+        Ok(WorkOutput::Markup {
+            status,
+            url,
+            text,
+            content_type,
+            headers: response.headers,
+        })
     }
 }
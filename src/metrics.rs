@@ -0,0 +1,197 @@
+use crate::frontier::Frontier;
+use crate::stats::{Stats, LATENCY_BUCKETS};
+use crate::{Job, Result, WorkResult};
+use async_std::channel::Receiver;
+use async_std::io::{ReadExt, WriteExt};
+use async_std::net::{TcpListener, TcpStream};
+use async_std::prelude::*;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+// limits for reading request head, slow or oversized requests are dropped
+const MAX_REQUEST_HEAD: usize = 8 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Live crawl counters rendered in Prometheus text exposition format
+pub(crate) struct Metrics {
+    pub(crate) stats: Arc<Stats>,
+    pub(crate) counter: Arc<AtomicUsize>,
+    pub(crate) workers: usize,
+    pub(crate) frontier: Arc<Frontier>,
+    pub(crate) workoutput_rx: Receiver<Job<WorkResult>>,
+}
+
+impl Metrics {
+    pub(crate) fn render(&self) -> String {
+        let stats = self.stats.snapshot();
+        let mut out = String::new();
+
+        out.push_str("# HELP crabler_queue_length Number of messages waiting in a queue.\n");
+        out.push_str("# TYPE crabler_queue_length gauge\n");
+        let _ = writeln!(
+            out,
+            "crabler_queue_length{{queue=\"input\"}} {}",
+            self.frontier.len()
+        );
+        let _ = writeln!(
+            out,
+            "crabler_queue_length{{queue=\"output\"}} {}",
+            self.workoutput_rx.len()
+        );
+
+        out.push_str("# HELP crabler_in_flight Number of scheduled requests not yet processed.\n");
+        out.push_str("# TYPE crabler_in_flight gauge\n");
+        let _ = writeln!(
+            out,
+            "crabler_in_flight {}",
+            self.counter.load(Ordering::SeqCst)
+        );
+
+        out.push_str("# HELP crabler_workers Number of started workers.\n");
+        out.push_str("# TYPE crabler_workers gauge\n");
+        let _ = writeln!(out, "crabler_workers {}", self.workers);
+
+        out.push_str("# HELP crabler_workers_busy Number of workers processing a request.\n");
+        out.push_str("# TYPE crabler_workers_busy gauge\n");
+        let _ = writeln!(out, "crabler_workers_busy {}", self.stats.busy_workers());
+
+        out.push_str("# HELP crabler_requests_total Number of responses received per host.\n");
+        out.push_str("# TYPE crabler_requests_total counter\n");
+        for (host, count) in &stats.hosts {
+            let _ = writeln!(
+                out,
+                "crabler_requests_total{{host=\"{}\"}} {}",
+                escape(host),
+                count
+            );
+        }
+
+        out.push_str("# HELP crabler_responses_total Number of responses received per status.\n");
+        out.push_str("# TYPE crabler_responses_total counter\n");
+        for (status, count) in &stats.statuses {
+            let _ = writeln!(
+                out,
+                "crabler_responses_total{{status=\"{}\"}} {}",
+                status, count
+            );
+        }
+
+        out.push_str("# HELP crabler_errors_total Number of failures per error kind.\n");
+        out.push_str("# TYPE crabler_errors_total counter\n");
+        for (kind, count) in &stats.errors {
+            let _ = writeln!(out, "crabler_errors_total{{kind=\"{}\"}} {}", kind, count);
+        }
+
+        out.push_str("# HELP crabler_duplicates_total Number of skipped already visited urls.\n");
+        out.push_str("# TYPE crabler_duplicates_total counter\n");
+        let _ = writeln!(out, "crabler_duplicates_total {}", stats.duplicates);
+
+        out.push_str("# HELP crabler_bytes_total Total size of downloaded bodies.\n");
+        out.push_str("# TYPE crabler_bytes_total counter\n");
+        let _ = writeln!(out, "crabler_bytes_total {}", stats.bytes);
+
+        out.push_str("# HELP crabler_request_duration_seconds Request latency.\n");
+        out.push_str("# TYPE crabler_request_duration_seconds histogram\n");
+        let mut cumulative = 0;
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(stats.latency.buckets.iter()) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "crabler_request_duration_seconds_bucket{{le=\"{}\"}} {}",
+                bound.as_secs_f64(),
+                cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "crabler_request_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            stats.latency.count()
+        );
+        let _ = writeln!(
+            out,
+            "crabler_request_duration_seconds_sum {}",
+            stats.latency.sum.as_secs_f64()
+        );
+        let _ = writeln!(
+            out,
+            "crabler_request_duration_seconds_count {}",
+            stats.latency.count()
+        );
+
+        out
+    }
+
+    /// Accept connections on given listener and answer every `GET /metrics`,
+    /// each connection is handled on its own task
+    pub(crate) async fn serve(self, listener: TcpListener) {
+        let metrics = Arc::new(self);
+        let mut incoming = listener.incoming();
+
+        while let Some(stream) = incoming.next().await {
+            match stream {
+                Ok(stream) => {
+                    let metrics = metrics.clone();
+                    async_std::task::spawn(async move {
+                        if let Err(e) = metrics.handle(stream).await {
+                            warn!("Failed to serve metrics: {}", e);
+                        }
+                    });
+                }
+                Err(e) => warn!("Failed to accept metrics connection: {}", e),
+            }
+        }
+    }
+
+    async fn handle(&self, mut stream: TcpStream) -> Result<()> {
+        let request = async_std::io::timeout(READ_TIMEOUT, read_head(&mut stream)).await?;
+        let request = String::from_utf8_lossy(&request);
+        let response = if request.starts_with("GET /metrics ") {
+            let body = self.render();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        } else {
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+        };
+
+        stream.write_all(response.as_bytes()).await?;
+        stream.flush().await?;
+
+        Ok(())
+    }
+}
+
+/// Read request up to the end of its headers
+async fn read_head(stream: &mut TcpStream) -> std::io::Result<Vec<u8>> {
+    let mut request = vec![];
+    let mut buf = [0; 1024];
+
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+        if request.len() > MAX_REQUEST_HEAD {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "request head is too large",
+            ));
+        }
+
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+
+    Ok(request)
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
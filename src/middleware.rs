@@ -0,0 +1,125 @@
+use crate::errors::*;
+use crate::fetcher::{FetchRequest, FetchResponse};
+use async_trait::async_trait;
+use std::fmt;
+use std::sync::Arc;
+
+/// What should happen with request once before-request hook is done
+#[derive(Debug)]
+pub enum RequestAction {
+    /// Pass (possibly modified) request to the next middleware and then to fetcher
+    Continue,
+    /// Skip fetcher and remaining middlewares, use given response instead
+    Respond(FetchResponse),
+    /// Don't make request at all, url is treated as skipped
+    Drop,
+}
+
+/// What should happen with response once after-response hook is done
+#[derive(Debug)]
+pub enum ResponseAction {
+    /// Pass (possibly modified) response on, eventually to handlers
+    Continue,
+    /// Discard response, handlers never see it
+    Drop,
+}
+
+/// Hooks around every request made by workers.
+/// Before-request hooks run in the order middlewares were added,
+/// after-response hooks run in reverse order.
+#[async_trait]
+pub trait Middleware: Send + Sync + 'static {
+    async fn before_request(&self, _request: &mut FetchRequest) -> Result<RequestAction> {
+        Ok(RequestAction::Continue)
+    }
+
+    async fn after_response(
+        &self,
+        _request: &FetchRequest,
+        _response: &mut FetchResponse,
+    ) -> Result<ResponseAction> {
+        Ok(ResponseAction::Continue)
+    }
+}
+
+/// Sets header on every request, replacing value set by previous middlewares
+#[derive(Clone, Debug)]
+pub struct HeaderMiddleware {
+    name: String,
+    value: String,
+}
+
+impl HeaderMiddleware {
+    pub fn new(name: &str, value: &str) -> Self {
+        HeaderMiddleware {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for HeaderMiddleware {
+    async fn before_request(&self, request: &mut FetchRequest) -> Result<RequestAction> {
+        request
+            .headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case(&self.name));
+        request
+            .headers
+            .push((self.name.clone(), self.value.clone()));
+
+        Ok(RequestAction::Continue)
+    }
+}
+
+/// Ordered chain of middlewares
+#[derive(Clone, Default)]
+pub struct Middlewares {
+    middlewares: Vec<Arc<dyn Middleware>>,
+}
+
+impl Middlewares {
+    pub(crate) fn add(&mut self, middleware: impl Middleware) {
+        self.middlewares.push(Arc::new(middleware));
+    }
+
+    /// Run before-request hooks until one of them stops the request,
+    /// returns number of hooks that ran together with final action
+    pub(crate) async fn before_request(
+        &self,
+        request: &mut FetchRequest,
+    ) -> Result<(usize, RequestAction)> {
+        for (i, middleware) in self.middlewares.iter().enumerate() {
+            match middleware.before_request(request).await? {
+                RequestAction::Continue => {}
+                action => return Ok((i + 1, action)),
+            }
+        }
+
+        Ok((self.middlewares.len(), RequestAction::Continue))
+    }
+
+    /// Run after-response hooks of middlewares that saw the request, in reverse order
+    pub(crate) async fn after_response(
+        &self,
+        ran: usize,
+        request: &FetchRequest,
+        response: &mut FetchResponse,
+    ) -> Result<ResponseAction> {
+        for middleware in self.middlewares[..ran].iter().rev() {
+            if let ResponseAction::Drop = middleware.after_response(request, response).await? {
+                return Ok(ResponseAction::Drop);
+            }
+        }
+
+        Ok(ResponseAction::Continue)
+    }
+}
+
+impl fmt::Debug for Middlewares {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Middlewares")
+            .field("middlewares", &self.middlewares.len())
+            .finish()
+    }
+}
@@ -0,0 +1,34 @@
+use serde_json::Value;
+
+/// Context attached to navigation request with `Response::navigate_with`,
+/// it is passed to the response of requested page
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Navigation {
+    /// User data, `Value::Null` when not set
+    pub meta: Value,
+    /// Name of the handler that should process requested page, crabler does not route on it,
+    /// handlers read it from `Response::handler`
+    pub handler: Option<String>,
+}
+
+impl Navigation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Attach user data, typed payloads can be converted with `serde_json::to_value`
+    pub fn with_meta(self, meta: Value) -> Self {
+        let mut new = self;
+        new.meta = meta;
+
+        new
+    }
+
+    /// Name the handler that should process requested page
+    pub fn with_handler(self, handler: &str) -> Self {
+        let mut new = self;
+        new.handler = Some(handler.to_string());
+
+        new
+    }
+}
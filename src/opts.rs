@@ -1,3 +1,11 @@
+use crate::frontier::Strategy;
+use crate::middleware::{HeaderMiddleware, Middleware, Middlewares};
+use crate::output::Output;
+use crate::pipeline::{ItemProcessor, ItemSink, Pipeline};
+use crate::shutdown::CancellationHandle;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
 pub type Urls = Vec<String>;
 // pub type Proxies = Vec<String>;
 pub type Threads = usize;
@@ -8,6 +16,55 @@ pub struct Opts {
     // pub proxies: Proxies,
     pub threads: Threads,
     pub follow_redirects: bool,
+    pub sitemaps: Urls,
+    pub discover_sitemaps: bool,
+    pub sitemap_modified_since: Option<DateTime<Utc>>,
+    pub follow_feed_items: bool,
+    pub pipeline: Pipeline,
+    pub middlewares: Middlewares,
+    pub cancellation: CancellationHandle,
+    pub shutdown_on_ctrl_c: bool,
+    pub shutdown_grace_period: Duration,
+    pub limits: Limits,
+    pub error_policy: ErrorPolicy,
+    pub strategy: Strategy,
+    pub queue_capacity: Option<usize>,
+    pub queue_spill_path: Option<String>,
+    pub warc_output: Option<String>,
+    pub warc_replay: Option<String>,
+    #[cfg(feature = "metrics")]
+    pub metrics_address: Option<String>,
+}
+
+/// Crawl budget, crawl is stopped as if it was cancelled once any limit is reached
+#[derive(Clone, Debug, Default)]
+pub struct Limits {
+    /// Total number of responses
+    pub pages: Option<usize>,
+    /// Total size of downloaded bodies
+    pub bytes: Option<u64>,
+    /// Total number of failed requests and handlers
+    pub errors: Option<usize>,
+    /// Wall-clock time since crawl was started with `start` or `run`
+    pub duration: Option<Duration>,
+}
+/// What happens when scraper handler, item processor or item sink returns error
+/// What happens when scraper handler returns error
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Stop crawl and return the error
+    #[default]
+    Abort,
+    /// Log error and carry on with the crawl
+    LogAndContinue,
+    /// Pass error to `#[on_error]` handler, crawl is aborted only if handler fails
+    Handler,
+}
+
+impl Default for Opts {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Opts {
@@ -17,6 +74,24 @@ impl Opts {
             // proxies: vec![],
             threads: 1,
             follow_redirects: true,
+            sitemaps: vec![],
+            discover_sitemaps: false,
+            sitemap_modified_since: None,
+            follow_feed_items: false,
+            pipeline: Pipeline::default(),
+            middlewares: Middlewares::default(),
+            cancellation: CancellationHandle::new(),
+            shutdown_on_ctrl_c: false,
+            shutdown_grace_period: Duration::from_secs(10),
+            limits: Limits::default(),
+            error_policy: ErrorPolicy::default(),
+            strategy: Strategy::default(),
+            queue_capacity: None,
+            queue_spill_path: None,
+            warc_output: None,
+            warc_replay: None,
+            #[cfg(feature = "metrics")]
+            metrics_address: None,
         }
     }
 
@@ -47,4 +122,193 @@ impl Opts {
 
         new
     }
+
+    /// Seed crawl with urls listed in given sitemaps or sitemap indexes
+    pub fn with_sitemaps(self, input: Vec<&str>) -> Self {
+        let mut new = self;
+        new.sitemaps = input.iter().map(|s| s.to_string()).collect();
+
+        new
+    }
+
+    /// Look up sitemaps for the hosts of seed urls, first in robots.txt
+    /// and then on well known paths like /sitemap.xml
+    pub fn with_sitemap_discovery(self, input: bool) -> Self {
+        let mut new = self;
+        new.discover_sitemaps = input;
+
+        new
+    }
+
+    /// Skip sitemap entries with lastmod older than given date
+    pub fn with_sitemap_modified_since(self, input: DateTime<Utc>) -> Self {
+        let mut new = self;
+        new.sitemap_modified_since = Some(input);
+
+        new
+    }
+
+    /// Visit link of every item found in RSS or Atom feeds
+    pub fn with_follow_feed_items(self, input: bool) -> Self {
+        let mut new = self;
+        new.follow_feed_items = input;
+
+        new
+    }
+
+    /// Append processor to the end of item pipeline,
+    /// processors run in the order they were added
+    pub fn with_item_processor(self, input: impl ItemProcessor + 'static) -> Self {
+        let mut new = self;
+        new.pipeline.add_processor(input);
+
+        new
+    }
+
+    /// Append middleware to the end of request chain
+    pub fn with_middleware(self, input: impl Middleware) -> Self {
+        let mut new = self;
+        new.middlewares.add(input);
+
+        new
+    }
+
+    /// Send given header with every request
+    pub fn with_header(self, name: &str, value: &str) -> Self {
+        self.with_middleware(HeaderMiddleware::new(name, value))
+    }
+
+    /// Add sink that will receive every item that passed all processors
+    pub fn with_item_sink(self, input: impl ItemSink + 'static) -> Self {
+        let mut new = self;
+        new.pipeline.add_sink(input);
+
+        new
+    }
+
+    /// Write emitted items to a file or database,
+    /// writes are buffered and flushed once crawl is done
+    pub fn with_output(self, input: Output) -> Self {
+        let mut new = self;
+        new.pipeline.add_sink(input.into_sink());
+
+        new
+    }
+
+    /// Stop crawl once given handle is cancelled
+    pub fn with_cancellation_handle(self, input: CancellationHandle) -> Self {
+        let mut new = self;
+        new.cancellation = input;
+
+        new
+    }
+
+    /// Stop crawl gracefully on Ctrl-C instead of killing the process
+    pub fn with_shutdown_on_ctrl_c(self, input: bool) -> Self {
+        let mut new = self;
+        new.shutdown_on_ctrl_c = input;
+
+        new
+    }
+
+    /// How long requests in flight are awaited after cancellation before they are aborted
+    pub fn with_shutdown_grace_period(self, input: Duration) -> Self {
+        let mut new = self;
+        new.shutdown_grace_period = input;
+
+        new
+    }
+
+    /// Order in which queued urls are visited, breadth-first by default
+    pub fn with_strategy(self, input: Strategy) -> Self {
+        let mut new = self;
+        new.strategy = input;
+
+        new
+    }
+
+    /// Keep at most given number of queued requests in memory,
+    /// handlers queuing more wait until workers catch up
+    pub fn with_queue_capacity(self, input: usize) -> Self {
+        let mut new = self;
+        new.queue_capacity = Some(input);
+
+        new
+    }
+
+    /// Write requests that don't fit into queue capacity to given file
+    /// instead of waiting for workers
+    pub fn with_queue_spill(self, input: &str) -> Self {
+        let mut new = self;
+        new.queue_spill_path = Some(input.to_string());
+
+        new
+    }
+
+    /// Record every request and response into WARC archive at given path,
+    /// archive is gzipped when path ends with `.gz`
+    pub fn with_warc_output(self, input: &str) -> Self {
+        let mut new = self;
+        new.warc_output = Some(input.to_string());
+
+        new
+    }
+
+    /// Serve responses from WARC archive at given path instead of network,
+    /// used by derived `run`, `run_with_stats` and `crawl`
+    pub fn with_warc_replay(self, input: &str) -> Self {
+        let mut new = self;
+        new.warc_replay = Some(input.to_string());
+
+        new
+    }
+
+    /// Stop crawl after given number of responses
+    pub fn with_max_pages(self, input: usize) -> Self {
+        let mut new = self;
+        new.limits.pages = Some(input);
+
+        new
+    }
+
+    /// Stop crawl after given number of bytes was downloaded
+    pub fn with_max_bytes(self, input: u64) -> Self {
+        let mut new = self;
+        new.limits.bytes = Some(input);
+
+        new
+    }
+
+    /// Set what happens when handler fails, crawl is aborted by default
+    pub fn with_error_policy(self, input: ErrorPolicy) -> Self {
+        let mut new = self;
+        new.error_policy = input;
+
+        new
+    }
+
+    /// Stop crawl after given number of failed requests and handlers
+    pub fn with_max_errors(self, input: usize) -> Self {
+        let mut new = self;
+        new.limits.errors = Some(input);
+
+        new
+    }
+
+    /// Stop crawl once it runs for given time
+    pub fn with_max_duration(self, input: Duration) -> Self {
+        let mut new = self;
+        new.limits.duration = Some(input);
+
+        new
+    }
+
+    /// Serve Prometheus metrics on `http://<address>/metrics` while crawl is running
+    #[cfg(feature = "metrics")]
+    pub fn with_metrics_endpoint(self, input: &str) -> Self {
+        let mut new = self;
+        new.metrics_address = Some(input.to_string());
+
+        new
+    }
 }
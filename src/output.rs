@@ -0,0 +1,311 @@
+use crate::errors::*;
+use crate::pipeline::{Item, ItemSink};
+use async_std::fs::File;
+use async_std::io::BufWriter;
+use async_std::prelude::*;
+use async_trait::async_trait;
+use serde_json::Value;
+use tracing::warn;
+
+/// Built-in sinks for emitted items
+#[derive(Clone, Debug)]
+pub enum Output {
+    /// One JSON document per line
+    JsonLines(String),
+    /// CSV file with header taken from the fields of the first item
+    Csv(String),
+    /// Table in a local SQLite database, created on first write
+    #[cfg(feature = "sqlite")]
+    Sqlite { path: String, table: String },
+}
+
+impl Output {
+    pub(crate) fn into_sink(self) -> Box<dyn ItemSink> {
+        match self {
+            Output::JsonLines(path) => Box::new(JsonLinesSink::new(path)),
+            Output::Csv(path) => Box::new(CsvSink::new(path)),
+            #[cfg(feature = "sqlite")]
+            Output::Sqlite { path, table } => Box::new(SqliteSink::new(path, table)),
+        }
+    }
+}
+
+/// Writes items as JSON Lines, file is created on first write
+pub struct JsonLinesSink {
+    path: String,
+    writer: Option<BufWriter<File>>,
+}
+
+impl JsonLinesSink {
+    pub fn new(path: String) -> Self {
+        JsonLinesSink { path, writer: None }
+    }
+
+    async fn writer(&mut self) -> Result<&mut BufWriter<File>> {
+        if self.writer.is_none() {
+            self.writer = Some(BufWriter::new(File::create(&self.path).await?));
+        }
+
+        Ok(self.writer.as_mut().unwrap())
+    }
+}
+
+#[async_trait(?Send)]
+impl ItemSink for JsonLinesSink {
+    async fn write(&mut self, item: Item) -> Result<()> {
+        let mut line = serde_json::to_vec(&item)?;
+        line.push(b'\n');
+        self.writer().await?.write_all(&line).await?;
+
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush().await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes items as CSV rows, columns are fixed by the first item,
+/// nested values are written as JSON. File is created on first write.
+pub struct CsvSink {
+    path: String,
+    header: Vec<String>,
+    writer: Option<BufWriter<File>>,
+}
+
+impl CsvSink {
+    pub fn new(path: String) -> Self {
+        CsvSink {
+            path,
+            header: vec![],
+            writer: None,
+        }
+    }
+
+    async fn writer(&mut self) -> Result<&mut BufWriter<File>> {
+        if self.writer.is_none() {
+            self.writer = Some(BufWriter::new(File::create(&self.path).await?));
+        }
+
+        Ok(self.writer.as_mut().unwrap())
+    }
+}
+
+#[async_trait(?Send)]
+impl ItemSink for CsvSink {
+    async fn write(&mut self, item: Item) -> Result<()> {
+        let fields = match item {
+            Value::Object(fields) => fields,
+            other => return Err(CrablerError::Output(format!("not a record: {}", other))),
+        };
+
+        if self.header.is_empty() {
+            self.header = fields.keys().cloned().collect();
+            let header = encode(&self.header)?;
+            self.writer().await?.write_all(&header).await?;
+        }
+
+        if let Some(key) = fields.keys().find(|key| !self.header.contains(key)) {
+            warn!("Field {} is not in CSV header, skipping it", key);
+        }
+
+        let row = self
+            .header
+            .iter()
+            .map(|key| fields.get(key).map(text).unwrap_or_default())
+            .collect::<Vec<_>>();
+        let row = encode(&row)?;
+        self.writer().await?.write_all(&row).await?;
+
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        if let Some(writer) = self.writer.as_mut() {
+            writer.flush().await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Inserts items into SQLite table, columns are fixed by the first item
+/// and rows are committed in batches
+#[cfg(feature = "sqlite")]
+pub struct SqliteSink {
+    path: String,
+    table: String,
+    columns: Vec<String>,
+    rows: Vec<Vec<rusqlite::types::Value>>,
+    connection: Option<rusqlite::Connection>,
+}
+
+#[cfg(feature = "sqlite")]
+impl SqliteSink {
+    const BATCH_SIZE: usize = 100;
+
+    pub fn new(path: String, table: String) -> Self {
+        SqliteSink {
+            path,
+            table,
+            columns: vec![],
+            rows: vec![],
+            connection: None,
+        }
+    }
+
+    /// Run blocking SQLite calls off the executor, connection is opened on first use
+    /// and moved to blocking thread and back
+    async fn with_connection<R, W>(&mut self, work: W) -> Result<R>
+    where
+        R: Send + 'static,
+        W: FnOnce(&mut rusqlite::Connection) -> Result<R> + Send + 'static,
+    {
+        let path = self.path.clone();
+        let connection = self.connection.take();
+
+        let (connection, result) = async_std::task::spawn_blocking(move || {
+            let mut connection = match connection {
+                Some(connection) => connection,
+                None => match rusqlite::Connection::open(&path) {
+                    Ok(connection) => connection,
+                    Err(e) => return (None, Err(output_error(e))),
+                },
+            };
+            let result = work(&mut connection);
+
+            (Some(connection), result)
+        })
+        .await;
+        self.connection = connection;
+
+        result
+    }
+
+    async fn create_table(&mut self) -> Result<()> {
+        let columns = self
+            .columns
+            .iter()
+            .map(|column| quote_identifier(column))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} ({})",
+            quote_identifier(&self.table),
+            columns
+        );
+
+        self.with_connection(move |connection| {
+            connection.execute(&sql, []).map_err(output_error)?;
+
+            Ok(())
+        })
+        .await
+    }
+
+    async fn insert_rows(&mut self) -> Result<()> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote_identifier(&self.table),
+            self.columns
+                .iter()
+                .map(|column| quote_identifier(column))
+                .collect::<Vec<_>>()
+                .join(", "),
+            vec!["?"; self.columns.len()].join(", ")
+        );
+        let rows = std::mem::take(&mut self.rows);
+
+        self.with_connection(move |connection| {
+            let transaction = connection.transaction().map_err(output_error)?;
+            {
+                let mut statement = transaction.prepare(&sql).map_err(output_error)?;
+                for row in rows {
+                    statement
+                        .execute(rusqlite::params_from_iter(row))
+                        .map_err(output_error)?;
+                }
+            }
+            transaction.commit().map_err(output_error)?;
+
+            Ok(())
+        })
+        .await
+    }
+}
+
+#[cfg(feature = "sqlite")]
+#[async_trait(?Send)]
+impl ItemSink for SqliteSink {
+    async fn write(&mut self, item: Item) -> Result<()> {
+        use rusqlite::types::Value as SqlValue;
+
+        let fields = match item {
+            Value::Object(fields) => fields,
+            other => return Err(CrablerError::Output(format!("not a record: {}", other))),
+        };
+
+        if self.columns.is_empty() {
+            self.columns = fields.keys().cloned().collect();
+            self.create_table().await?;
+        }
+
+        let row = self
+            .columns
+            .iter()
+            .map(|column| match fields.get(column) {
+                None | Some(Value::Null) => SqlValue::Null,
+                Some(Value::Bool(b)) => SqlValue::Integer(*b as i64),
+                Some(Value::Number(n)) => match n.as_i64() {
+                    Some(i) => SqlValue::Integer(i),
+                    None => SqlValue::Real(n.as_f64().unwrap_or_default()),
+                },
+                Some(value) => SqlValue::Text(text(value)),
+            })
+            .collect();
+        self.rows.push(row);
+
+        if self.rows.len() >= Self::BATCH_SIZE {
+            self.insert_rows().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        self.insert_rows().await
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Encodes CSV record in memory, so that it can be written with async I/O
+fn encode(record: &[String]) -> Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(record).map_err(output_error)?;
+
+    writer.into_inner().map_err(output_error)
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn output_error(e: impl std::fmt::Display) -> CrablerError {
+    CrablerError::Output(e.to_string())
+}
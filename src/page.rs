@@ -0,0 +1,79 @@
+use crate::fetcher::find_header;
+use crate::Response;
+use crabquery::Document;
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+/// Successfully loaded page passed to `on_page` handlers,
+/// derefs to `Response` so that it can be used to navigate further
+#[derive(Clone)]
+pub struct Page {
+    pub response: Response,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    document: Rc<Document>,
+}
+
+impl Page {
+    pub(crate) fn new(
+        response: Response,
+        headers: Vec<(String, String)>,
+        body: String,
+        document: Rc<Document>,
+    ) -> Self {
+        Page {
+            response,
+            headers,
+            body,
+            document,
+        }
+    }
+
+    /// First value of given header, name is case-insensitive
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Parsed page, shared with `on_html` handlers
+    pub fn document(&self) -> &Document {
+        &self.document
+    }
+}
+
+impl Deref for Page {
+    type Target = Response;
+
+    fn deref(&self) -> &Response {
+        &self.response
+    }
+}
+
+impl DerefMut for Page {
+    fn deref_mut(&mut self) -> &mut Response {
+        &mut self.response
+    }
+}
+
+/// Argument of `on_page` handler, handlers can take `Page`, `Response`
+/// or just the page body as `String`
+pub trait FromPage {
+    fn from_page(page: &Page) -> Self;
+}
+
+impl FromPage for Page {
+    fn from_page(page: &Page) -> Self {
+        page.clone()
+    }
+}
+
+impl FromPage for Response {
+    fn from_page(page: &Page) -> Self {
+        page.response.clone()
+    }
+}
+
+impl FromPage for String {
+    fn from_page(page: &Page) -> Self {
+        page.body.clone()
+    }
+}
@@ -0,0 +1,97 @@
+use crate::errors::*;
+use async_std::sync::Mutex;
+use async_trait::async_trait;
+use std::fmt;
+use std::sync::Arc;
+
+/// Item emitted by scraper handlers through `Response::emit`
+pub type Item = serde_json::Value;
+
+/// Step of item pipeline, can validate, enrich or drop items by returning `None`
+#[async_trait(?Send)]
+pub trait ItemProcessor: Send {
+    async fn process(&mut self, item: Item) -> Result<Option<Item>>;
+}
+
+#[async_trait(?Send)]
+impl<F> ItemProcessor for F
+where
+    F: FnMut(Item) -> Result<Option<Item>> + Send,
+{
+    async fn process(&mut self, item: Item) -> Result<Option<Item>> {
+        self(item)
+    }
+}
+
+/// Final destination of items that made it through all processors
+#[async_trait(?Send)]
+pub trait ItemSink: Send {
+    async fn write(&mut self, item: Item) -> Result<()>;
+
+    /// Called once crawl is done
+    async fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[async_trait(?Send)]
+impl ItemSink for Box<dyn ItemSink> {
+    async fn write(&mut self, item: Item) -> Result<()> {
+        (**self).write(item).await
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        (**self).flush().await
+    }
+}
+
+/// Ordered chain of item processors followed by sinks
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    processors: Vec<Arc<Mutex<dyn ItemProcessor>>>,
+    sinks: Vec<Arc<Mutex<dyn ItemSink>>>,
+}
+
+impl Pipeline {
+    pub(crate) fn add_processor(&mut self, processor: impl ItemProcessor + 'static) {
+        self.processors.push(Arc::new(Mutex::new(processor)));
+    }
+
+    pub(crate) fn add_sink(&mut self, sink: impl ItemSink + 'static) {
+        self.sinks.push(Arc::new(Mutex::new(sink)));
+    }
+
+    pub(crate) async fn process(&self, item: Item) -> Result<()> {
+        let mut item = item;
+
+        for processor in self.processors.iter() {
+            match processor.lock().await.process(item).await? {
+                Some(processed) => item = processed,
+                None => return Ok(()),
+            }
+        }
+
+        for sink in self.sinks.iter() {
+            sink.lock().await.write(item.clone()).await?;
+        }
+
+        Ok(())
+    }
+
+    pub(crate) async fn flush(&self) -> Result<()> {
+        for sink in self.sinks.iter() {
+            sink.lock().await.flush().await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pipeline")
+            .field("processors", &self.processors.len())
+            .field("sinks", &self.sinks.len())
+            .finish()
+    }
+}
@@ -0,0 +1,49 @@
+use async_std::channel::{bounded, Receiver, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Stops running crawl from any task or thread.
+/// No new work is queued after cancellation, requests in flight get
+/// a grace period to finish and crawl returns stats collected so far.
+#[derive(Clone, Debug)]
+pub struct CancellationHandle {
+    cancelled: Arc<AtomicBool>,
+    tx: Sender<()>,
+    rx: Receiver<()>,
+}
+
+impl Default for CancellationHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CancellationHandle {
+    pub fn new() -> Self {
+        let (tx, rx) = bounded(1);
+
+        CancellationHandle {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            tx,
+            rx,
+        }
+    }
+
+    /// Request crawl to stop, calling it more than once has no effect
+    pub fn cancel(&self) {
+        if !self.cancelled.swap(true, Ordering::SeqCst) {
+            let _ = self.tx.try_send(());
+        }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once `cancel` is called
+    pub(crate) async fn cancelled(&self) {
+        if !self.is_cancelled() {
+            let _ = self.rx.recv().await;
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use crate::errors::*;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use flate2::read::GzDecoder;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::Read;
+use surf::Url;
+
+/// Paths probed for a sitemap when robots.txt does not list any
+const WELL_KNOWN_SITEMAPS: [&str; 2] = ["/sitemap.xml", "/sitemap_index.xml"];
+
+/// Single `<url>` or `<sitemap>` entry found in a sitemap file
+#[derive(Clone, Debug, Default)]
+pub struct SitemapEntry {
+    pub loc: String,
+    pub lastmod: Option<DateTime<Utc>>,
+    pub changefreq: Option<String>,
+    pub priority: Option<f32>,
+}
+
+impl SitemapEntry {
+    /// Returns false only when entry has lastmod that is older than given date,
+    /// entries without lastmod are always considered fresh
+    pub fn modified_since(&self, since: &DateTime<Utc>) -> bool {
+        match &self.lastmod {
+            Some(lastmod) => lastmod >= since,
+            None => true,
+        }
+    }
+
+    fn set(&mut self, field: &[u8], value: &str) {
+        match field {
+            b"loc" => self.loc = value.to_string(),
+            b"lastmod" => self.lastmod = parse_w3c_datetime(value),
+            b"changefreq" => self.changefreq = Some(value.to_string()),
+            b"priority" => self.priority = value.parse().ok(),
+            _ => {}
+        }
+    }
+}
+
+/// Parsed sitemap file, sitemap index files populate `sitemaps`
+/// while url sets populate `urls`
+#[derive(Debug, Default)]
+pub(crate) struct Sitemap {
+    pub sitemaps: Vec<SitemapEntry>,
+    pub urls: Vec<SitemapEntry>,
+}
+
+/// Parse sitemap index or url set, gzipped payloads are detected by magic bytes
+pub(crate) fn parse_sitemap(bytes: &[u8]) -> Result<Sitemap> {
+    let bytes = gunzip(bytes)?;
+    let mut reader = Reader::from_reader(bytes.as_slice());
+    reader.trim_text(true);
+
+    let mut buf = vec![];
+    let mut sitemap = Sitemap::default();
+    let mut depth = 0;
+    let mut entry: Option<(usize, SitemapEntry)> = None;
+    let mut field: Option<Vec<u8>> = None;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                depth += 1;
+                let name = e.local_name();
+
+                match &entry {
+                    None if name.as_ref() == b"url" || name.as_ref() == b"sitemap" => {
+                        entry = Some((depth, SitemapEntry::default()));
+                    }
+                    // ignore extension tags like <image:loc> nested deeper in the entry
+                    Some((entry_depth, _)) if depth == entry_depth + 1 => {
+                        field = Some(name.as_ref().to_vec());
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::Text(text)) => {
+                if let (Some((_, entry)), Some(field)) = (entry.as_mut(), &field) {
+                    let value = text
+                        .unescape()
+                        .map_err(|e| CrablerError::BodyParsing(format!("sitemap: {}", e)))?;
+                    entry.set(field, value.trim());
+                }
+            }
+            Ok(Event::CData(text)) => {
+                if let (Some((_, entry)), Some(field)) = (entry.as_mut(), &field) {
+                    let value = String::from_utf8_lossy(&text);
+                    entry.set(field, value.trim());
+                }
+            }
+            Ok(Event::End(e)) => {
+                let name = e.local_name();
+
+                match entry.take() {
+                    Some((entry_depth, parsed)) if entry_depth == depth => {
+                        if parsed.loc.is_empty() {
+                            // nothing to follow
+                        } else if name.as_ref() == b"sitemap" {
+                            sitemap.sitemaps.push(parsed);
+                        } else {
+                            sitemap.urls.push(parsed);
+                        }
+                    }
+                    other => entry = other,
+                }
+
+                field = None;
+                depth -= 1;
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(CrablerError::BodyParsing(format!(
+                    "sitemap at position {}: {}",
+                    reader.buffer_position(),
+                    e
+                )))
+            }
+            _ => {}
+        }
+
+        buf.clear();
+    }
+
+    Ok(sitemap)
+}
+
+/// Collect urls from `Sitemap:` lines of robots.txt
+pub(crate) fn parse_robots(text: &str) -> Vec<String> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.split('#').next().unwrap_or("");
+            let (key, value) = line.split_once(':')?;
+
+            if key.trim().eq_ignore_ascii_case("sitemap") && !value.trim().is_empty() {
+                Some(value.trim().to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// robots.txt location for the host of given url
+pub(crate) fn robots_url(url: &str) -> Result<String> {
+    join(url, "/robots.txt")
+}
+
+/// Well known sitemap locations for the host of given url
+pub(crate) fn well_known_sitemaps(url: &str) -> Result<Vec<String>> {
+    WELL_KNOWN_SITEMAPS
+        .iter()
+        .map(|path| join(url, path))
+        .collect()
+}
+
+fn join(url: &str, path: &str) -> Result<String> {
+    Url::parse(url)
+        .and_then(|base| base.join(path))
+        .map(|url| url.to_string())
+        .map_err(|e| CrablerError::InvalidUrl(url.to_string(), e.to_string()))
+}
+
+fn gunzip(bytes: &[u8]) -> Result<Vec<u8>> {
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut decoded = vec![];
+        GzDecoder::new(bytes).read_to_end(&mut decoded)?;
+
+        Ok(decoded)
+    } else {
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Parse W3C datetime used by sitemaps, date-only values are treated as UTC midnight
+pub(crate) fn parse_w3c_datetime(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+
+    if let Ok(datetime) = DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M%:z") {
+        return Some(datetime.with_timezone(&Utc));
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|datetime| Utc.from_utc_datetime(&datetime))
+}
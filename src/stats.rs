@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use surf::Url;
+
+/// Upper bounds of latency histogram buckets, last bucket catches everything else
+pub const LATENCY_BUCKETS: [Duration; 10] = [
+    Duration::from_millis(10),
+    Duration::from_millis(25),
+    Duration::from_millis(50),
+    Duration::from_millis(100),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_millis(2500),
+    Duration::from_secs(5),
+    Duration::from_secs(10),
+];
+
+/// Counters shared between crabler and its workers
+#[derive(Debug)]
+pub(crate) struct Stats {
+    // set when crawl starts, not when crabler is created
+    started: Mutex<Option<Instant>>,
+    statuses: Mutex<BTreeMap<u16, usize>>,
+    hosts: Mutex<BTreeMap<String, usize>>,
+    errors: Mutex<BTreeMap<&'static str, usize>>,
+    duplicates: AtomicUsize,
+    bytes: AtomicU64,
+    latency: [AtomicUsize; LATENCY_BUCKETS.len() + 1],
+    latency_sum: AtomicU64,
+    busy_workers: AtomicUsize,
+    finish_reason: Mutex<FinishReason>,
+}
+
+impl Stats {
+    pub(crate) fn new() -> Self {
+        Stats {
+            started: Mutex::new(None),
+            statuses: Mutex::new(BTreeMap::new()),
+            hosts: Mutex::new(BTreeMap::new()),
+            errors: Mutex::new(BTreeMap::new()),
+            duplicates: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+            latency: Default::default(),
+            latency_sum: AtomicU64::new(0),
+            busy_workers: AtomicUsize::new(0),
+            finish_reason: Mutex::new(FinishReason::Completed),
+        }
+    }
+
+    /// Record finished request with its status, body size and time it took
+    pub(crate) fn record_response(&self, url: &str, status: u16, bytes: usize, latency: Duration) {
+        *self.statuses.lock().unwrap().entry(status).or_insert(0) += 1;
+
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_string()))
+            .unwrap_or_default();
+        *self.hosts.lock().unwrap().entry(host).or_insert(0) += 1;
+
+        self.bytes.fetch_add(bytes as u64, Ordering::SeqCst);
+
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| latency <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.latency[bucket].fetch_add(1, Ordering::SeqCst);
+        self.latency_sum
+            .fetch_add(latency.as_micros() as u64, Ordering::SeqCst);
+    }
+
+    pub(crate) fn record_error(&self, kind: &'static str) {
+        *self.errors.lock().unwrap().entry(kind).or_insert(0) += 1;
+    }
+
+    pub(crate) fn record_duplicate(&self) {
+        self.duplicates.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Record why crawl has ended, first recorded reason wins
+    pub(crate) fn finish(&self, reason: FinishReason) {
+        let mut finish_reason = self.finish_reason.lock().unwrap();
+        if *finish_reason == FinishReason::Completed {
+            *finish_reason = reason;
+        }
+    }
+
+    /// Start crawl clock, only the first call counts
+    pub(crate) fn start_clock(&self) {
+        self.started
+            .lock()
+            .unwrap()
+            .get_or_insert_with(Instant::now);
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        self.started
+            .lock()
+            .unwrap()
+            .map(|started| started.elapsed())
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn worker_busy(&self) {
+        self.busy_workers.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub(crate) fn worker_idle(&self) {
+        self.busy_workers.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    /// Number of workers currently processing a message
+    #[cfg(feature = "metrics")]
+    pub(crate) fn busy_workers(&self) -> usize {
+        self.busy_workers.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn snapshot(&self) -> CrawlStats {
+        let latency = LatencyHistogram {
+            buckets: self
+                .latency
+                .iter()
+                .map(|count| count.load(Ordering::SeqCst))
+                .collect(),
+            sum: Duration::from_micros(self.latency_sum.load(Ordering::SeqCst)),
+        };
+
+        CrawlStats {
+            statuses: self.statuses.lock().unwrap().clone(),
+            hosts: self.hosts.lock().unwrap().clone(),
+            errors: self
+                .errors
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(kind, count)| (kind.to_string(), *count))
+                .collect(),
+            duplicates: self.duplicates.load(Ordering::SeqCst),
+            bytes: self.bytes.load(Ordering::SeqCst),
+            latency,
+            elapsed: self.elapsed(),
+            finish_reason: *self.finish_reason.lock().unwrap(),
+        }
+    }
+}
+
+/// Counters collected during crawl
+#[derive(Clone, Debug, Default)]
+pub struct CrawlStats {
+    /// Number of responses per HTTP status
+    pub statuses: BTreeMap<u16, usize>,
+    /// Number of responses per host
+    pub hosts: BTreeMap<String, usize>,
+    /// Number of failures per `CrablerError` variant
+    pub errors: BTreeMap<String, usize>,
+    /// Number of requests skipped because url was already visited
+    pub duplicates: usize,
+    /// Total size of downloaded bodies
+    pub bytes: u64,
+    pub latency: LatencyHistogram,
+    pub elapsed: Duration,
+    pub finish_reason: FinishReason,
+}
+
+/// Why crawl has ended
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FinishReason {
+    /// All scheduled work was processed
+    #[default]
+    Completed,
+    /// Crawl was stopped with `CancellationHandle` or Ctrl-C
+    Cancelled,
+    /// `Limits::pages` responses were received
+    PageLimit,
+    /// `Limits::bytes` were downloaded
+    ByteLimit,
+    /// `Limits::errors` failures were recorded
+    ErrorLimit,
+    /// Crawl ran for `Limits::duration`
+    TimeLimit,
+}
+
+impl CrawlStats {
+    /// Total number of responses received
+    pub fn pages(&self) -> usize {
+        self.statuses.values().sum()
+    }
+
+    /// Number of responses with status in given class, e.g. `2` for 2xx
+    pub fn status_class(&self, class: u16) -> usize {
+        self.statuses
+            .iter()
+            .filter(|(status, _)| *status / 100 == class)
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    /// Total number of failures
+    pub fn failures(&self) -> usize {
+        self.errors.values().sum()
+    }
+}
+
+/// Request latencies grouped into `LATENCY_BUCKETS`
+#[derive(Clone, Debug, Default)]
+pub struct LatencyHistogram {
+    /// Number of requests per bucket, last element counts requests slower than every bound
+    pub buckets: Vec<usize>,
+    pub sum: Duration,
+}
+
+impl LatencyHistogram {
+    pub fn count(&self) -> usize {
+        self.buckets.iter().sum()
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        match self.count() {
+            0 => None,
+            count => Some(self.sum / count as u32),
+        }
+    }
+}
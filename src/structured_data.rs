@@ -0,0 +1,169 @@
+use crabquery::{Document, Element};
+use serde_json::Value;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Structured data found on a page: schema.org JSON-LD blocks, microdata items
+/// and OpenGraph/Twitter card meta properties
+#[derive(Clone, Debug, Default)]
+pub struct StructuredData {
+    pub json_ld: Vec<Value>,
+    pub microdata: Vec<MicrodataItem>,
+    pub opengraph: Vec<(String, String)>,
+    pub twitter: Vec<(String, String)>,
+}
+
+/// Element marked with `itemscope` together with its `itemprop` values
+#[derive(Clone, Debug, Default)]
+pub struct MicrodataItem {
+    pub item_type: Vec<String>,
+    pub id: Option<String>,
+    pub properties: HashMap<String, Vec<MicrodataValue>>,
+}
+
+#[derive(Clone, Debug)]
+pub enum MicrodataValue {
+    Text(String),
+    Item(MicrodataItem),
+}
+
+impl StructuredData {
+    /// Extract all structured data from given document
+    pub fn extract(document: &Document) -> Self {
+        let mut data = StructuredData::default();
+
+        for script in document.select("script") {
+            if script.attr("type").as_deref() == Some("application/ld+json") {
+                data.push_json_ld(&script.text().unwrap_or_default());
+            }
+        }
+
+        for el in document.select("[itemscope]") {
+            // items used as property values are collected by their parent item
+            if el.attr("itemprop").is_none() {
+                data.microdata.push(MicrodataItem::from_element(&el));
+            }
+        }
+
+        for meta in document.select("meta") {
+            let key = meta.attr("property").or_else(|| meta.attr("name"));
+
+            if let (Some(key), Some(content)) = (key, meta.attr("content")) {
+                if key.starts_with("og:") {
+                    data.opengraph.push((key, content));
+                } else if key.starts_with("twitter:") {
+                    data.twitter.push((key, content));
+                }
+            }
+        }
+
+        data
+    }
+
+    /// First value of OpenGraph property, like `og:title`
+    pub fn opengraph_property(&self, property: &str) -> Option<&str> {
+        find_property(&self.opengraph, property)
+    }
+
+    /// First value of Twitter card property, like `twitter:card`
+    pub fn twitter_property(&self, property: &str) -> Option<&str> {
+        find_property(&self.twitter, property)
+    }
+
+    fn push_json_ld(&mut self, text: &str) {
+        match serde_json::from_str(text) {
+            Ok(Value::Array(values)) => self.json_ld.extend(values),
+            Ok(value) => self.json_ld.push(value),
+            Err(e) => warn!("Skipping malformed JSON-LD block: {}", e),
+        }
+    }
+}
+
+impl MicrodataItem {
+    fn from_element(el: &Element) -> Self {
+        let mut item = MicrodataItem {
+            item_type: el
+                .attr("itemtype")
+                .map(|t| t.split_whitespace().map(|t| t.to_string()).collect())
+                .unwrap_or_default(),
+            id: el.attr("itemid"),
+            properties: HashMap::new(),
+        };
+
+        item.collect_properties(el);
+        item
+    }
+
+    /// First text value of given property
+    pub fn property(&self, name: &str) -> Option<&str> {
+        self.properties
+            .get(name)
+            .and_then(|values| values.iter().find_map(MicrodataValue::as_text))
+    }
+
+    fn collect_properties(&mut self, el: &Element) {
+        for child in el.children() {
+            let nested = child.attr("itemscope").is_some();
+
+            if let Some(names) = child.attr("itemprop") {
+                let value = if nested {
+                    MicrodataValue::Item(MicrodataItem::from_element(&child))
+                } else {
+                    MicrodataValue::Text(property_value(&child))
+                };
+
+                for name in names.split_whitespace() {
+                    self.properties
+                        .entry(name.to_string())
+                        .or_default()
+                        .push(value.clone());
+                }
+            }
+
+            // properties below nested item belong to that item
+            if !nested {
+                self.collect_properties(&child);
+            }
+        }
+    }
+}
+
+impl MicrodataValue {
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            MicrodataValue::Text(text) => Some(text),
+            MicrodataValue::Item(_) => None,
+        }
+    }
+
+    pub fn as_item(&self) -> Option<&MicrodataItem> {
+        match self {
+            MicrodataValue::Item(item) => Some(item),
+            MicrodataValue::Text(_) => None,
+        }
+    }
+}
+
+fn property_value(el: &Element) -> String {
+    let attr = match el.tag().as_deref() {
+        Some("meta") => el.attr("content"),
+        Some("a") | Some("area") | Some("link") => el.attr("href"),
+        Some("img") | Some("audio") | Some("video") | Some("source") | Some("iframe")
+        | Some("embed") | Some("track") => el.attr("src"),
+        Some("object") => el.attr("data"),
+        Some("time") => el.attr("datetime"),
+        Some("data") | Some("meter") => el.attr("value"),
+        _ => None,
+    };
+
+    attr.or_else(|| el.text())
+        .map(|value| value.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn find_property<'a>(properties: &'a [(String, String)], property: &str) -> Option<&'a str> {
+    properties
+        .iter()
+        .find(|(key, _)| key == property)
+        .map(|(_, value)| value.as_str())
+}
@@ -0,0 +1,76 @@
+use crate::errors::*;
+use regex::Regex;
+use surf::Url;
+
+/// Compiled url pattern, see `url_matches` for the syntax.
+/// Derived scrapers compile every pattern once and keep it in a static.
+pub struct UrlPattern {
+    regex: Regex,
+    // match against path and query instead of the whole url
+    path_only: bool,
+}
+
+impl UrlPattern {
+    pub fn new(pattern: &str) -> Result<Self> {
+        let invalid =
+            |e: regex::Error| CrablerError::UrlPattern(pattern.to_string(), e.to_string());
+
+        let regex = if pattern.starts_with('^') {
+            Regex::new(pattern).map_err(invalid)?
+        } else {
+            Regex::new(&glob_to_regex(pattern)).map_err(invalid)?
+        };
+        let path_only = pattern.trim_start_matches('^').starts_with('/');
+
+        Ok(UrlPattern { regex, path_only })
+    }
+
+    pub fn is_match(&self, url: &str) -> bool {
+        if !self.path_only {
+            return self.regex.is_match(url);
+        }
+
+        match Url::parse(url) {
+            Ok(url) => {
+                let path = match url.query() {
+                    Some(query) => format!("{}?{}", url.path(), query),
+                    None => url.path().to_string(),
+                };
+
+                self.regex.is_match(&path)
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    regex.push('$');
+    regex
+}
+
+/// Check whether url matches given pattern, used by `on_url` handlers and `url` scopes.
+/// Patterns starting with `^` are regular expressions, anything else is a glob
+/// where `*` matches anything but `/`, `**` matches anything and `?` matches any one character.
+/// `?` is a wildcard in globs, `/search?q=*` matches `/search?q=rust` but also `/searchXq=rust`.
+/// Patterns starting with `/` (or `^/`) are matched against url path and query,
+/// other patterns against the whole url.
+/// Pattern is compiled on every call, use `UrlPattern` to match many urls.
+pub fn url_matches(pattern: &str, url: &str) -> Result<bool> {
+    Ok(UrlPattern::new(pattern)?.is_match(url))
+}
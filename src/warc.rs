@@ -0,0 +1,322 @@
+use crate::errors::*;
+use crate::fetcher::{FetchRequest, FetchResponse, Fetcher, MemoryFetcher};
+use async_std::fs::File;
+use async_std::prelude::*;
+use async_std::sync::Mutex;
+use async_trait::async_trait;
+use chrono::{SecondsFormat, Utc};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use surf::{StatusCode, Url};
+
+// headers describing transfer of original body, body is stored decoded
+const SKIPPED_HEADERS: [&str; 3] = ["content-length", "content-encoding", "transfer-encoding"];
+
+/// Writes every request and response made by workers into WARC archive.
+/// File is created on first record, archives ending with `.gz` are gzipped per record.
+pub(crate) struct WarcWriter {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+    seq: AtomicU64,
+}
+
+impl WarcWriter {
+    pub(crate) fn new(path: &str) -> Self {
+        WarcWriter {
+            path: PathBuf::from(path),
+            file: Mutex::new(None),
+            seq: AtomicU64::new(0),
+        }
+    }
+
+    /// Build request and response records for one exchange,
+    /// they are built before taking the file lock so that only `append` is serialized
+    pub(crate) fn records(
+        &self,
+        request: &FetchRequest,
+        response: &FetchResponse,
+        body: &[u8],
+    ) -> Result<Vec<u8>> {
+        let request_id = self.record_id();
+        let request_record = self.record(
+            "request",
+            &request_id,
+            Some(&request.url),
+            "application/http;msgtype=request",
+            &[],
+            &request_block(request)?,
+        )?;
+        let response_record = self.record(
+            "response",
+            &self.record_id(),
+            Some(&request.url),
+            "application/http;msgtype=response",
+            &[("WARC-Concurrent-To", &request_id)],
+            &response_block(response, body),
+        )?;
+
+        let mut records = request_record;
+        records.extend_from_slice(&response_record);
+
+        Ok(records)
+    }
+
+    /// Append records to archive, warcinfo record is written first
+    pub(crate) async fn append(&self, records: Vec<u8>) -> Result<()> {
+        let mut file = self.file.lock().await;
+        if file.is_none() {
+            let mut created = File::create(&self.path).await?;
+            created.write_all(&self.warcinfo()?).await?;
+            *file = Some(created);
+        }
+
+        if let Some(file) = file.as_mut() {
+            file.write_all(&records).await?;
+            file.flush().await?;
+        }
+
+        Ok(())
+    }
+
+    fn warcinfo(&self) -> Result<Vec<u8>> {
+        let info = format!(
+            "software: crabler/{}\r\nformat: WARC File Format 1.1\r\n",
+            env!("CARGO_PKG_VERSION")
+        );
+
+        self.record(
+            "warcinfo",
+            &self.record_id(),
+            None,
+            "application/warc-fields",
+            &[],
+            info.as_bytes(),
+        )
+    }
+
+    fn record(
+        &self,
+        kind: &str,
+        id: &str,
+        target: Option<&str>,
+        content_type: &str,
+        extra: &[(&str, &str)],
+        block: &[u8],
+    ) -> Result<Vec<u8>> {
+        let mut head = format!(
+            "WARC/1.1\r\nWARC-Type: {}\r\nWARC-Record-ID: {}\r\nWARC-Date: {}\r\n",
+            kind,
+            id,
+            Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+        );
+        if let Some(target) = target {
+            head.push_str(&format!("WARC-Target-URI: {}\r\n", target));
+        }
+        for (name, value) in extra {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str(&format!(
+            "Content-Type: {}\r\nContent-Length: {}\r\n\r\n",
+            content_type,
+            block.len()
+        ));
+
+        let mut record = head.into_bytes();
+        record.extend_from_slice(block);
+        record.extend_from_slice(b"\r\n\r\n");
+
+        if self.path.to_string_lossy().ends_with(".gz") {
+            let mut encoder = GzEncoder::new(vec![], Compression::default());
+            encoder.write_all(&record)?;
+            record = encoder.finish()?;
+        }
+
+        Ok(record)
+    }
+
+    fn record_id(&self) -> String {
+        let seq = self.seq.fetch_add(1, Ordering::SeqCst);
+        let nanos = Utc::now().timestamp_nanos_opt().unwrap_or_default() as u64;
+        let low = (std::process::id() as u64) << 32 | (seq & 0xffff_ffff);
+
+        format!(
+            "<urn:uuid:{:08x}-{:04x}-{:04x}-{:04x}-{:012x}>",
+            nanos >> 32,
+            (nanos >> 16) & 0xffff,
+            nanos & 0xffff,
+            low >> 48,
+            low & 0xffff_ffff_ffff
+        )
+    }
+}
+
+fn request_block(request: &FetchRequest) -> Result<Vec<u8>> {
+    let url = Url::parse(&request.url)
+        .map_err(|e| CrablerError::InvalidUrl(request.url.clone(), e.to_string()))?;
+    let target = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+
+    let mut block = format!("GET {} HTTP/1.1\r\n", target);
+    if request.header("host").is_none() {
+        let host = url.host_str().unwrap_or_default();
+        match url.port() {
+            Some(port) => block.push_str(&format!("Host: {}:{}\r\n", host, port)),
+            None => block.push_str(&format!("Host: {}\r\n", host)),
+        }
+    }
+    for (name, value) in request.headers.iter() {
+        block.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    block.push_str("\r\n");
+
+    Ok(block.into_bytes())
+}
+
+fn response_block(response: &FetchResponse, body: &[u8]) -> Vec<u8> {
+    let reason = StatusCode::try_from(response.status)
+        .map(|status| status.canonical_reason())
+        .unwrap_or_default();
+
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason);
+    for (name, value) in response.headers.iter() {
+        if !SKIPPED_HEADERS.contains(&name.to_lowercase().as_str()) {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    head.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+
+    let mut block = head.into_bytes();
+    block.extend_from_slice(body);
+    block
+}
+
+/// Fetcher serving responses recorded in WARC archive instead of the network,
+/// urls that are not in archive respond with 404.
+/// When url was recorded more than once, the last response wins.
+#[derive(Clone, Debug)]
+pub struct WarcReplay {
+    pages: MemoryFetcher,
+}
+
+impl WarcReplay {
+    /// Load all responses from plain or gzipped WARC archive
+    pub fn open(path: &str) -> Result<Self> {
+        let mut data = std::fs::read(path)?;
+        if data.starts_with(&[0x1f, 0x8b]) {
+            let mut decoded = vec![];
+            MultiGzDecoder::new(data.as_slice()).read_to_end(&mut decoded)?;
+            data = decoded;
+        }
+
+        let mut pages = MemoryFetcher::new();
+        let mut rest = data.as_slice();
+
+        while let Some(Record {
+            headers,
+            block,
+            rest: remaining,
+        }) = next_record(rest)?
+        {
+            rest = remaining;
+
+            let header = |name: &str| {
+                headers
+                    .iter()
+                    .find(|(header, _)| header.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| value.as_str())
+            };
+
+            if let (Some("response"), Some(url)) = (header("WARC-Type"), header("WARC-Target-URI"))
+            {
+                let (status, headers, body) = parse_response(block)?;
+                let headers = headers
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str()))
+                    .collect();
+                pages = pages.with_response(url, status, headers, body.to_vec());
+            }
+        }
+
+        Ok(WarcReplay { pages })
+    }
+}
+
+#[async_trait]
+impl Fetcher for WarcReplay {
+    async fn fetch(&self, request: FetchRequest) -> Result<FetchResponse> {
+        self.pages.fetch(request).await
+    }
+}
+
+type Headers = Vec<(String, String)>;
+
+/// Split head of the message from its body and parse header lines
+fn split_head(data: &[u8]) -> Result<(String, Headers, &[u8])> {
+    let end = data
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(|| CrablerError::Warc("missing end of headers".to_string()))?;
+
+    let head = String::from_utf8_lossy(&data[..end]);
+    let mut lines = head.split("\r\n");
+    let first = lines.next().unwrap_or_default().to_string();
+    let headers = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+
+    Ok((first, headers, &data[end + 4..]))
+}
+
+/// WARC record split out of archive data
+struct Record<'a> {
+    headers: Headers,
+    block: &'a [u8],
+    // data following the record
+    rest: &'a [u8],
+}
+
+fn next_record(data: &[u8]) -> Result<Option<Record<'_>>> {
+    let start = match data.iter().position(|b| !b.is_ascii_whitespace()) {
+        Some(start) => start,
+        None => return Ok(None),
+    };
+
+    let (version, headers, rest) = split_head(&data[start..])?;
+    if !version.starts_with("WARC/") {
+        return Err(CrablerError::Warc(format!("unexpected record {}", version)));
+    }
+
+    let length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .ok_or_else(|| CrablerError::Warc("missing record length".to_string()))?;
+    if rest.len() < length {
+        return Err(CrablerError::Warc("truncated record".to_string()));
+    }
+
+    Ok(Some(Record {
+        headers,
+        block: &rest[..length],
+        rest: &rest[length..],
+    }))
+}
+
+fn parse_response(block: &[u8]) -> Result<(u16, Headers, &[u8])> {
+    let (status_line, headers, body) = split_head(block)?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| CrablerError::Warc(format!("invalid status line {}", status_line)))?;
+
+    Ok((status, headers, body))
+}
@@ -0,0 +1,103 @@
+extern crate crabler;
+
+use crabler::*;
+
+const PAGES: usize = 20;
+const COPIES: usize = 20;
+
+#[derive(WebScraper)]
+#[on_html("a[href]", link_handler)]
+struct DuplicateLinksScraper {
+    base: String,
+}
+
+impl DuplicateLinksScraper {
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        response.navigate(format!("{}{}", self.base, href)).await
+    }
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+fn links() -> String {
+    (0..PAGES)
+        .flat_map(|i| (0..COPIES).map(move |_| format!(r#"<a href="/page/{}">{}</a>"#, i, i)))
+        .collect()
+}
+
+#[async_std::test]
+async fn test_each_url_is_fetched_once() {
+    let mut server = new_mock_server().await;
+    let base = server.url();
+    let index = format!("{}/", base);
+
+    let mut mocks = vec![server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_body(links())
+        .expect(1)
+        .create()];
+    for i in 0..PAGES {
+        // every page links to every other page again
+        mocks.push(
+            server
+                .mock("GET", format!("/page/{}", i).as_str())
+                .with_status(200)
+                .with_body(links())
+                .expect(1)
+                .create(),
+        );
+    }
+
+    let stats = DuplicateLinksScraper { base }
+        .run_with_stats(
+            Opts::new()
+                .with_urls(vec![index.as_str()])
+                .with_threads(32)
+                // redirect middleware would issue extra requests and skew hit counts
+                .with_follow_redirects(false),
+        )
+        .await
+        .unwrap();
+
+    for mock in &mocks {
+        mock.assert_async().await;
+    }
+    assert_eq!(stats.pages(), PAGES + 1);
+    assert_eq!(stats.duplicates, (PAGES + 1) * PAGES * COPIES - PAGES);
+}
+
+#[async_std::test]
+async fn test_robots_txt_is_fetched_once() {
+    let mut server = new_mock_server().await;
+    let base = server.url();
+
+    let robots = server
+        .mock("GET", "/robots.txt")
+        .with_status(200)
+        .with_body("User-agent: *\n")
+        .expect(1)
+        .create();
+    let _sitemap = server.mock("GET", "/sitemap.xml").with_status(404).create();
+
+    let opts = Opts::new().with_follow_redirects(false);
+    let mut crabler = Crabler::new(DuplicateLinksScraper { base: base.clone() }, &opts);
+
+    // sitemap discovery is not deduplicated by the queue, so every copy reaches a worker
+    // and workers alone have to make sure that robots.txt is claimed once
+    for i in 0..PAGES * COPIES {
+        crabler
+            .discover_sitemaps(&format!("{}/page/{}", base, i))
+            .await
+            .unwrap();
+    }
+    for _ in 0..32 {
+        crabler.start_worker();
+    }
+    crabler.run().await.unwrap();
+
+    robots.assert_async().await;
+}
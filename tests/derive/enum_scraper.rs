@@ -0,0 +1,9 @@
+use crabler::*;
+
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+enum Scraper {
+    Empty,
+}
+
+fn main() {}
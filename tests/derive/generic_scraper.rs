@@ -0,0 +1,49 @@
+use crabler::*;
+
+trait Store {
+    fn save(&mut self, url: String);
+}
+
+#[derive(Default)]
+struct MemoryStore {
+    urls: Vec<String>,
+}
+
+impl Store for MemoryStore {
+    fn save(&mut self, url: String) {
+        self.urls.push(url);
+    }
+}
+
+/// Scraper with injected storage backend
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+#[allow(dead_code)]
+struct Scraper<S: Store, const N: usize>
+where
+    S: Default,
+{
+    store: S,
+    limits: [usize; N],
+}
+
+impl<S: Store + Default, const N: usize> Scraper<S, N> {
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        self.store.save(response.url);
+        Ok(())
+    }
+}
+
+async fn crawl() -> Result<MemoryStore> {
+    let scraper = Scraper {
+        store: MemoryStore::default(),
+        limits: [1, 2],
+    };
+    let scraper = scraper.crawl(Opts::new()).await?;
+
+    Ok(scraper.store)
+}
+
+fn main() {
+    let _ = crawl();
+}
@@ -0,0 +1,13 @@
+use crabler::*;
+
+#[derive(WebScraper)]
+#[on_url("^/product/(", product_handler)]
+struct Scraper {}
+
+impl Scraper {
+    async fn product_handler(&mut self, _response: Response) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn main() {}
@@ -0,0 +1,37 @@
+use crabler::*;
+
+#[derive(WebScraper)]
+#[on_html("a[href]", link_handler)]
+#[on_page(page_handler)]
+struct Scraper<'a> {
+    links: &'a mut Vec<String>,
+    prefix: &'a str,
+}
+
+impl<'a> Scraper<'a> {
+    async fn link_handler(&mut self, _response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            self.links.push(format!("{}{}", self.prefix, href));
+        }
+        Ok(())
+    }
+
+    async fn page_handler(&mut self, page: Page) -> Result<()> {
+        self.links.push(page.url.clone());
+        Ok(())
+    }
+}
+
+async fn crawl(links: &mut Vec<String>) -> Result<()> {
+    let scraper = Scraper {
+        links,
+        prefix: "http://example.com",
+    };
+
+    scraper.run(Opts::new()).await
+}
+
+fn main() {
+    let mut links = vec![];
+    let _ = crawl(&mut links);
+}
@@ -0,0 +1,20 @@
+use crabler::*;
+
+trait Store {
+    fn save(&mut self, url: String);
+}
+
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+struct Scraper<S> {
+    store: S,
+}
+
+impl<S: Store> Scraper<S> {
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        self.store.save(response.url);
+        Ok(())
+    }
+}
+
+fn main() {}
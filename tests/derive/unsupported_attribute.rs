@@ -0,0 +1,7 @@
+use crabler::*;
+
+#[derive(WebScraper)]
+#[on_response = "response_handler"]
+struct Scraper {}
+
+fn main() {}
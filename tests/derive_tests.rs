@@ -0,0 +1,10 @@
+#[test]
+fn test_web_scraper_derive() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/derive/generic_scraper.rs");
+    t.pass("tests/derive/lifetime_scraper.rs");
+    t.compile_fail("tests/derive/enum_scraper.rs");
+    t.compile_fail("tests/derive/unsupported_attribute.rs");
+    t.compile_fail("tests/derive/missing_bounds.rs");
+    t.compile_fail("tests/derive/invalid_url_pattern.rs");
+}
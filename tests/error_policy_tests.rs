@@ -0,0 +1,145 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[derive(WebScraper)]
+#[on_html("a[href]", link_handler)]
+#[on_html("p.broken", broken_handler)]
+#[on_error(error_handler)]
+struct FailingScraper {
+    pages: Vec<String>,
+    errors: Vec<(String, &'static str, Option<String>, String)>,
+}
+
+impl FailingScraper {
+    fn new() -> Self {
+        FailingScraper {
+            pages: vec![],
+            errors: vec![],
+        }
+    }
+
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        self.pages.push(response.url.clone());
+        let href = a.attr("href").unwrap();
+        response
+            .navigate(format!("http://example.com{}", href))
+            .await
+    }
+
+    async fn broken_handler(&mut self, response: Response, _p: Element) -> Result<()> {
+        Err(CrablerError::BodyParsing(format!(
+            "broken paragraph on {}",
+            response.url
+        )))
+    }
+
+    async fn error_handler(&mut self, context: ErrorContext, error: CrablerError) -> Result<()> {
+        self.errors.push((
+            context.url,
+            context.hook,
+            context.selector,
+            error.to_string(),
+        ));
+        Ok(())
+    }
+}
+
+#[derive(WebScraper)]
+#[on_html("p.broken", broken_handler)]
+struct UnhandledScraper {}
+
+impl UnhandledScraper {
+    async fn broken_handler(&mut self, _response: Response, _p: Element) -> Result<()> {
+        Err(CrablerError::BodyParsing("broken".to_string()))
+    }
+}
+
+fn site() -> MemoryFetcher {
+    MemoryFetcher::new()
+        .with_page(
+            "http://example.com/",
+            r#"<p class="broken">x</p><a href="/one">1</a>"#,
+        )
+        .with_page(
+            "http://example.com/one",
+            r#"<p class="broken">x</p><a href="/two">2</a>"#,
+        )
+        .with_page(
+            "http://example.com/two",
+            r#"<p class="broken">x</p><a href="/">home</a>"#,
+        )
+}
+
+async fn crawl(opts: Opts) -> (Result<CrawlStats>, FailingScraper) {
+    let opts = opts.with_urls(vec!["http://example.com/"]);
+    let mut crabler = Crabler::with_fetcher(FailingScraper::new(), &opts, site());
+    let result = crabler.start(&opts).await;
+
+    (result, crabler.into_scraper())
+}
+
+#[async_std::test]
+async fn test_abort_by_default() {
+    let (result, scraper) = crawl(Opts::new()).await;
+
+    assert!(matches!(result, Err(CrablerError::BodyParsing(_))));
+    assert!(scraper.errors.is_empty());
+}
+
+#[async_std::test]
+async fn test_log_and_continue() {
+    let (result, scraper) = crawl(Opts::new().with_error_policy(ErrorPolicy::LogAndContinue)).await;
+    let stats = result.unwrap();
+
+    assert_eq!(scraper.pages.len(), 3);
+    assert_eq!(stats.errors.get("BodyParsing"), Some(&3));
+    assert!(scraper.errors.is_empty());
+}
+
+#[async_std::test]
+async fn test_error_handler() {
+    let (result, scraper) = crawl(Opts::new().with_error_policy(ErrorPolicy::Handler)).await;
+    let stats = result.unwrap();
+
+    assert_eq!(scraper.pages.len(), 3);
+    assert_eq!(stats.failures(), 3);
+    assert_eq!(
+        scraper.errors[0],
+        (
+            "http://example.com/".to_string(),
+            "on_html",
+            Some("p.broken".to_string()),
+            "body parsing error: broken paragraph on http://example.com/".to_string()
+        )
+    );
+}
+
+#[async_std::test]
+async fn test_max_errors_threshold() {
+    let (result, scraper) = crawl(
+        Opts::new()
+            .with_error_policy(ErrorPolicy::Handler)
+            .with_threads(1)
+            .with_max_errors(2),
+    )
+    .await;
+    let stats = result.unwrap();
+
+    assert_eq!(stats.finish_reason, FinishReason::ErrorLimit);
+    assert!(scraper.errors.len() >= 2);
+    assert!(
+        scraper.errors.len() <= 3,
+        "Only response in flight may exceed threshold"
+    );
+}
+
+#[async_std::test]
+async fn test_handler_policy_without_handler_aborts() {
+    let opts = Opts::new()
+        .with_urls(vec!["http://example.com/"])
+        .with_error_policy(ErrorPolicy::Handler);
+    let mut crabler = Crabler::with_fetcher(UnhandledScraper {}, &opts, site());
+
+    assert!(crabler.start(&opts).await.is_err());
+}
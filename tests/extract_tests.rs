@@ -0,0 +1,116 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, RwLock};
+
+#[derive(Extract, Debug)]
+struct Product {
+    #[css(attr = "data-sku")]
+    sku: String,
+    #[css("h2", text)]
+    title: String,
+    #[css("span.price")]
+    price: f64,
+    #[css("a.details", attr = "href")]
+    link: Option<String>,
+    #[css("li.tag")]
+    tags: Vec<String>,
+    seen: bool,
+}
+
+#[derive(WebScraper)]
+#[on_record(Product, "div.product", product_handler)]
+struct ProductScraper {
+    products: Arc<RwLock<Vec<Product>>>,
+}
+
+impl ProductScraper {
+    async fn product_handler(&mut self, _response: Response, product: Product) -> Result<()> {
+        self.products.write().unwrap().push(product);
+        Ok(())
+    }
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+#[async_std::test]
+async fn test_records_extracted() {
+    let mut server = new_mock_server().await;
+
+    let _listing = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body(
+            r#"<html><body>
+                <div class="product" data-sku="A-1">
+                    <h2> Anvil </h2>
+                    <span class="price">119.99</span>
+                    <a class="details" href="/anvil">details</a>
+                    <ul><li class="tag">heavy</li><li class="tag">iron</li></ul>
+                </div>
+                <div class="product" data-sku="B-2">
+                    <h2>Rocket skates</h2>
+                    <span class="price">45</span>
+                </div>
+            </body></html>"#,
+        )
+        .create();
+
+    let products = Arc::new(RwLock::new(vec![]));
+    let scraper = ProductScraper {
+        products: products.clone(),
+    };
+    let url = server.url();
+
+    scraper
+        .run(Opts::new().with_urls(vec![url.as_str()]))
+        .await
+        .unwrap();
+
+    let products = products.read().unwrap();
+    assert_eq!(products.len(), 2);
+
+    assert_eq!(products[0].sku, "A-1");
+    assert_eq!(products[0].title, "Anvil");
+    assert_eq!(products[0].price, 119.99);
+    assert_eq!(products[0].link.as_deref(), Some("/anvil"));
+    assert_eq!(products[0].tags, vec!["heavy", "iron"]);
+    assert!(!products[0].seen);
+
+    assert_eq!(products[1].title, "Rocket skates");
+    assert_eq!(products[1].price, 45.0);
+    assert_eq!(products[1].link, None);
+    assert!(products[1].tags.is_empty());
+}
+
+#[async_std::test]
+async fn test_record_conversion_error() {
+    let mut server = new_mock_server().await;
+
+    let _listing = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body(
+            r#"<div class="product" data-sku="C-3">
+                <h2>Giant magnet</h2>
+                <span class="price">call us</span>
+            </div>"#,
+        )
+        .create();
+
+    let scraper = ProductScraper {
+        products: Arc::new(RwLock::new(vec![])),
+    };
+    let url = server.url();
+
+    let result = scraper.run(Opts::new().with_urls(vec![url.as_str()])).await;
+
+    match result {
+        Err(CrablerError::Extract(field, _)) => assert_eq!(field, "price"),
+        other => panic!("Expected extract error, got {:?}", other),
+    }
+}
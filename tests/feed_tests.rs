@@ -0,0 +1,195 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, RwLock};
+
+type Seen<T> = Arc<RwLock<Vec<T>>>;
+
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+#[on_feed_item(feed_item_handler)]
+struct FeedScraper {
+    visited: Seen<String>,
+    items: Seen<FeedItem>,
+}
+
+impl FeedScraper {
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        self.visited.write().unwrap().push(response.url);
+        Ok(())
+    }
+
+    async fn feed_item_handler(&mut self, _response: Response, item: FeedItem) -> Result<()> {
+        self.items.write().unwrap().push(item);
+        Ok(())
+    }
+}
+
+fn make_scraper() -> (FeedScraper, Seen<String>, Seen<FeedItem>) {
+    let visited = Arc::new(RwLock::new(vec![]));
+    let items = Arc::new(RwLock::new(vec![]));
+    let scraper = FeedScraper {
+        visited: visited.clone(),
+        items: items.clone(),
+    };
+    (scraper, visited, items)
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+#[async_std::test]
+async fn test_rss_items_followed() {
+    let mut server = new_mock_server().await;
+    let base = server.url();
+
+    let _feed = server
+        .mock("GET", "/rss")
+        .with_status(200)
+        .with_header("content-type", "application/rss+xml; charset=utf-8")
+        .with_body(format!(
+            r#"<?xml version="1.0"?>
+            <rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom">
+              <channel>
+                <title>News</title>
+                <link>{0}</link>
+                <atom:link href="{0}/rss" rel="self" type="application/rss+xml"/>
+                <item>
+                  <title>First &amp; foremost</title>
+                  <link>{0}/news/1</link>
+                  <guid isPermaLink="false">news-1</guid>
+                  <pubDate>Tue, 10 Jun 2003 04:00:00 GMT</pubDate>
+                </item>
+                <item>
+                  <title><![CDATA[Second]]></title>
+                  <link>{0}/news/2</link>
+                </item>
+              </channel>
+            </rss>"#,
+            base
+        ))
+        .create();
+
+    let _first = server
+        .mock("GET", "/news/1")
+        .with_status(200)
+        .with_body("<html></html>")
+        .create();
+    let _second = server
+        .mock("GET", "/news/2")
+        .with_status(200)
+        .with_body("<html></html>")
+        .create();
+
+    let (scraper, visited, items) = make_scraper();
+    let url = format!("{}/rss", base);
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![url.as_str()])
+                .with_follow_feed_items(true),
+        )
+        .await
+        .unwrap();
+
+    let items = items.read().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0].title.as_deref(), Some("First & foremost"));
+    assert_eq!(items[0].link, Some(format!("{}/news/1", base)));
+    assert_eq!(items[0].guid.as_deref(), Some("news-1"));
+    assert_eq!(
+        items[0].published,
+        Some("2003-06-10T04:00:00Z".parse().unwrap())
+    );
+    assert_eq!(items[1].title.as_deref(), Some("Second"));
+    assert_eq!(items[1].published, None);
+
+    let visited = visited.read().unwrap();
+    assert!(visited.contains(&format!("{}/news/1", base)));
+    assert!(visited.contains(&format!("{}/news/2", base)));
+}
+
+#[async_std::test]
+async fn test_atom_entries_sniffed_from_xml() {
+    let mut server = new_mock_server().await;
+    let base = server.url();
+
+    let _feed = server
+        .mock("GET", "/atom")
+        .with_status(200)
+        .with_header("content-type", "application/xml")
+        .with_body(format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom">
+              <title>Example Feed</title>
+              <link href="{0}/"/>
+              <entry>
+                <title>Atom-Powered Robots Run Amok</title>
+                <link rel="edit" href="{0}/edit/1"/>
+                <link href="{0}/2003/12/13/atom03"/>
+                <id>urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a</id>
+                <updated>2003-12-14T18:30:02Z</updated>
+                <published>2003-12-13T18:30:02+01:00</published>
+              </entry>
+            </feed>"#,
+            base
+        ))
+        .create();
+
+    let article = server
+        .mock("GET", "/2003/12/13/atom03")
+        .with_status(200)
+        .expect(0)
+        .create();
+
+    let (scraper, _visited, items) = make_scraper();
+    let url = format!("{}/atom", base);
+
+    scraper
+        .run(Opts::new().with_urls(vec![url.as_str()]))
+        .await
+        .unwrap();
+
+    let items = items.read().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].link, Some(format!("{}/2003/12/13/atom03", base)));
+    assert_eq!(
+        items[0].guid.as_deref(),
+        Some("urn:uuid:1225c695-cfb8-4ebb-aaaa-80da344efa6a")
+    );
+    assert_eq!(
+        items[0].published,
+        Some("2003-12-13T17:30:02Z".parse().unwrap())
+    );
+
+    // item links are not visited unless following is enabled
+    article.assert();
+}
+
+#[async_std::test]
+async fn test_malformed_feed_follows_error_policy() {
+    let fetcher = MemoryFetcher::new()
+        .with_response(
+            "http://example.com/rss",
+            200,
+            vec![("Content-Type", "application/rss+xml")],
+            b"<rss><channel><item></channel></rss>".to_vec(),
+        )
+        .with_page("http://example.com/", "home");
+    let opts = Opts::new()
+        .with_urls(vec!["http://example.com/rss", "http://example.com/"])
+        .with_error_policy(ErrorPolicy::LogAndContinue);
+
+    let (scraper, visited, items) = make_scraper();
+    let mut crabler = Crabler::with_fetcher(scraper, &opts, fetcher);
+    let stats = crabler.start(&opts).await.unwrap();
+
+    assert!(items.read().unwrap().is_empty());
+    assert!(visited
+        .read()
+        .unwrap()
+        .contains(&"http://example.com/".to_string()));
+    assert_eq!(stats.errors.values().sum::<usize>(), 1);
+}
@@ -0,0 +1,76 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct MemoryScraper {
+    visited: Vec<(String, u16)>,
+}
+
+impl MemoryScraper {
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        self.visited.push((response.url.clone(), response.status));
+        Ok(())
+    }
+
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        response
+            .navigate(format!("http://example.com{}", href))
+            .await
+    }
+}
+
+#[async_std::test]
+async fn test_crawl_with_memory_fetcher() {
+    let fetcher = MemoryFetcher::new()
+        .with_page(
+            "http://example.com/",
+            r#"<a href="/one">1</a><a href="/missing">2</a>"#,
+        )
+        .with_page("http://example.com/one", r#"<a href="/">home</a>"#);
+    let opts = Opts::new().with_urls(vec!["http://example.com/"]);
+
+    let mut crabler = Crabler::with_fetcher(MemoryScraper { visited: vec![] }, &opts, fetcher);
+    let stats = crabler.start(&opts).await.unwrap();
+
+    let mut visited = crabler.into_scraper().visited;
+    visited.sort();
+    assert_eq!(
+        visited,
+        vec![
+            ("http://example.com/".to_string(), 200),
+            ("http://example.com/missing".to_string(), 404),
+            ("http://example.com/one".to_string(), 200),
+        ]
+    );
+    assert_eq!(stats.duplicates, 1);
+}
+
+#[async_std::test]
+async fn test_memory_fetcher_response() {
+    let fetcher = MemoryFetcher::new().with_response(
+        "http://example.com/feed",
+        200,
+        vec![("Content-Type", "application/rss+xml; charset=utf-8")],
+        b"<rss/>".to_vec(),
+    );
+
+    let mut response = fetcher
+        .fetch(FetchRequest::get("http://example.com/feed"))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status, 200);
+    assert_eq!(
+        response.content_type(),
+        Some("application/rss+xml".to_string())
+    );
+    assert_eq!(
+        response.header("content-type"),
+        Some("application/rss+xml; charset=utf-8")
+    );
+    assert_eq!(response.bytes().await.unwrap(), b"<rss/>".to_vec());
+}
@@ -0,0 +1,138 @@
+extern crate crabler;
+
+use crabler::*;
+use std::time::Duration;
+
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct OrderScraper {
+    base: String,
+    visited: Vec<String>,
+}
+
+impl OrderScraper {
+    fn new(base: &str) -> Self {
+        OrderScraper {
+            base: base.to_string(),
+            visited: vec![],
+        }
+    }
+
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        let path = response.url.trim_start_matches(&self.base).to_string();
+        self.visited.push(path);
+        Ok(())
+    }
+
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        let priority = a
+            .attr("data-priority")
+            .map(|p| p.parse().unwrap())
+            .unwrap_or_default();
+
+        response
+            .navigate_with_priority(format!("{}{}", self.base, href), priority)
+            .await
+    }
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+fn mock_pages(server: &mut mockito::ServerGuard) -> Vec<mockito::Mock> {
+    vec![
+        // keeps the only worker busy while the rest of links is queued
+        server
+            .mock("GET", "/slow")
+            .with_status(200)
+            .with_body_from_request(|_| {
+                std::thread::sleep(Duration::from_millis(300));
+                b"slow".to_vec()
+            })
+            .create(),
+        server
+            .mock("GET", mockito::Matcher::Regex("^/[a-z]+$".to_string()))
+            .with_status(200)
+            .with_body("page")
+            .create(),
+    ]
+}
+
+async fn crawl_links(strategy: Strategy) -> Vec<String> {
+    let mut server = new_mock_server().await;
+    let base = server.url();
+    let _index = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_body(
+            r#"<a href="/slow" data-priority="100">slow</a>
+               <a href="/a">a</a>
+               <a href="/bb">bb</a>
+               <a href="/ccc">ccc</a>"#,
+        )
+        .create();
+    let _pages = mock_pages(&mut server);
+
+    OrderScraper::new(&base)
+        .crawl(
+            Opts::new()
+                .with_urls(vec![base.as_str()])
+                .with_strategy(strategy),
+        )
+        .await
+        .unwrap()
+        .visited
+}
+
+#[async_std::test]
+async fn test_breadth_first() {
+    assert_eq!(
+        crawl_links(Strategy::BreadthFirst).await,
+        vec!["", "/slow", "/a", "/bb", "/ccc"]
+    );
+}
+
+#[async_std::test]
+async fn test_depth_first() {
+    assert_eq!(
+        crawl_links(Strategy::DepthFirst).await,
+        vec!["", "/slow", "/ccc", "/bb", "/a"]
+    );
+}
+
+#[async_std::test]
+async fn test_best_first() {
+    let strategy = Strategy::best_first(|url, _depth| if url.ends_with("/bb") { 1.0 } else { 0.0 });
+
+    assert_eq!(
+        crawl_links(strategy).await,
+        vec!["", "/slow", "/bb", "/a", "/ccc"]
+    );
+}
+
+#[async_std::test]
+async fn test_navigate_with_priority() {
+    let mut server = new_mock_server().await;
+    let base = server.url();
+    let _index = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_body(
+            r#"<a href="/slow" data-priority="100">slow</a>
+               <a href="/low" data-priority="-1">low</a>
+               <a href="/high" data-priority="10">high</a>
+               <a href="/mid">mid</a>"#,
+        )
+        .create();
+    let _pages = mock_pages(&mut server);
+
+    let scraper = OrderScraper::new(&base)
+        .crawl(Opts::new().with_urls(vec![base.as_str()]))
+        .await
+        .unwrap();
+
+    assert_eq!(scraper.visited, vec!["", "/slow", "/high", "/mid", "/low"]);
+}
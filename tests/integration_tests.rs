@@ -26,11 +26,9 @@ impl TestScraper {
     }
 }
 
-fn make_scraper() -> (
-    TestScraper,
-    Arc<RwLock<Vec<u16>>>,
-    Arc<RwLock<Vec<String>>>,
-) {
+type Seen<T> = Arc<RwLock<Vec<T>>>;
+
+fn make_scraper() -> (TestScraper, Seen<u16>, Seen<String>) {
     let responses_seen = Arc::new(RwLock::new(vec![]));
     let links_seen = Arc::new(RwLock::new(vec![]));
     let scraper = TestScraper {
@@ -0,0 +1,110 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[derive(WebScraper)]
+#[on_start(start_handler)]
+#[on_response(response_handler)]
+#[on_finish(finish_handler)]
+struct LifecycleScraper {
+    events: Vec<String>,
+    pages: Option<usize>,
+}
+
+impl LifecycleScraper {
+    fn new() -> Self {
+        LifecycleScraper {
+            events: vec![],
+            pages: None,
+        }
+    }
+
+    async fn start_handler(&mut self, mut response: Response) -> Result<()> {
+        self.events.push("start".to_string());
+        response
+            .navigate("http://example.com/login".to_string())
+            .await
+    }
+
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        self.events.push(response.url.clone());
+        Ok(())
+    }
+
+    async fn finish_handler(&mut self, stats: CrawlStats) -> Result<()> {
+        self.events.push("finish".to_string());
+        self.pages = Some(stats.pages());
+        Ok(())
+    }
+}
+
+fn site() -> MemoryFetcher {
+    MemoryFetcher::new()
+        .with_page("http://example.com/login", "login")
+        .with_page("http://example.com/", "index")
+}
+
+#[async_std::test]
+async fn test_start_and_finish_handlers() {
+    let opts = Opts::new()
+        .with_urls(vec!["http://example.com/"])
+        .with_threads(1);
+    let mut crabler = Crabler::with_fetcher(LifecycleScraper::new(), &opts, site());
+    let stats = crabler.start(&opts).await.unwrap();
+    let scraper = crabler.into_scraper();
+
+    assert_eq!(
+        scraper.events,
+        vec![
+            "start",
+            "http://example.com/login",
+            "http://example.com/",
+            "finish"
+        ],
+        "Requests queued on start go before seeds"
+    );
+    assert_eq!(scraper.pages, Some(2));
+    assert_eq!(stats.pages(), 2);
+}
+
+#[async_std::test]
+async fn test_start_handler_without_seeds() {
+    let opts = Opts::new();
+    let mut crabler = Crabler::with_fetcher(LifecycleScraper::new(), &opts, site());
+    crabler.start(&opts).await.unwrap();
+
+    assert_eq!(
+        crabler.into_scraper().events,
+        vec!["start", "http://example.com/login", "finish"]
+    );
+}
+
+#[derive(WebScraper)]
+#[on_response(failing_handler)]
+#[on_finish(finish_handler)]
+struct FailingScraper {
+    finished: bool,
+}
+
+impl FailingScraper {
+    async fn failing_handler(&mut self, _response: Response) -> Result<()> {
+        Err(CrablerError::BodyParsing("broken".to_string()))
+    }
+
+    async fn finish_handler(&mut self, _stats: CrawlStats) -> Result<()> {
+        self.finished = true;
+        Err(CrablerError::BodyParsing("teardown".to_string()))
+    }
+}
+
+#[async_std::test]
+async fn test_finish_handler_runs_after_failed_crawl() {
+    let opts = Opts::new().with_urls(vec!["http://example.com/"]);
+    let mut crabler = Crabler::with_fetcher(FailingScraper { finished: false }, &opts, site());
+
+    match crabler.start(&opts).await {
+        Err(CrablerError::BodyParsing(message)) => assert_eq!(message, "broken"),
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert!(crabler.into_scraper().finished);
+}
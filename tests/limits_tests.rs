@@ -0,0 +1,178 @@
+extern crate crabler;
+
+use crabler::*;
+use std::time::{Duration, Instant};
+
+#[derive(WebScraper)]
+#[on_html("a[href]", link_handler)]
+struct LinkScraper {
+    base: String,
+}
+
+impl LinkScraper {
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        let url = if href.starts_with("http") {
+            href
+        } else {
+            format!("{}{}", self.base, href)
+        };
+
+        response.navigate(url).await
+    }
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+fn mock_site(server: &mut mockito::ServerGuard, links: &str) -> Vec<mockito::Mock> {
+    vec![
+        server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_body(links)
+            .create(),
+        server
+            .mock("GET", mockito::Matcher::Regex("^/page/\\d+$".to_string()))
+            .with_status(200)
+            .with_body("page")
+            .create(),
+    ]
+}
+
+#[async_std::test]
+async fn test_max_pages() {
+    let mut server = new_mock_server().await;
+    let links = (0..10)
+        .map(|i| format!(r#"<a href="/page/{}">{}</a>"#, i, i))
+        .collect::<String>();
+    let _mocks = mock_site(&mut server, &links);
+    let base = server.url();
+
+    let stats = LinkScraper { base: base.clone() }
+        .run_with_stats(Opts::new().with_urls(vec![base.as_str()]).with_max_pages(3))
+        .await
+        .unwrap();
+
+    assert_eq!(stats.finish_reason, FinishReason::PageLimit);
+    assert!(stats.pages() >= 3);
+    assert!(
+        stats.pages() <= 4,
+        "Only request in flight may exceed limit"
+    );
+}
+
+#[async_std::test]
+async fn test_max_bytes() {
+    let mut server = new_mock_server().await;
+    let links = (0..10)
+        .map(|i| format!(r#"<a href="/page/{}">{}</a>"#, i, i))
+        .collect::<String>();
+    let _mocks = mock_site(&mut server, &links);
+    let base = server.url();
+
+    let stats = LinkScraper { base: base.clone() }
+        .run_with_stats(
+            Opts::new()
+                .with_urls(vec![base.as_str()])
+                .with_max_bytes(10),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(stats.finish_reason, FinishReason::ByteLimit);
+    assert!(stats.pages() <= 2);
+}
+
+#[async_std::test]
+async fn test_max_errors() {
+    let mut server = new_mock_server().await;
+    let links = (1..5)
+        .map(|i| format!(r#"<a href="http://127.0.0.1:{}/">{}</a>"#, i, i))
+        .collect::<String>();
+    let _mocks = mock_site(&mut server, &links);
+    let base = server.url();
+
+    let stats = LinkScraper { base: base.clone() }
+        .run_with_stats(
+            Opts::new()
+                .with_urls(vec![base.as_str()])
+                .with_max_errors(1),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(stats.finish_reason, FinishReason::ErrorLimit);
+    assert!(stats.failures() <= 2);
+}
+
+#[async_std::test]
+async fn test_max_duration() {
+    let mut server = new_mock_server().await;
+    let _slow = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_body_from_request(|_| {
+            std::thread::sleep(Duration::from_secs(3));
+            b"slow".to_vec()
+        })
+        .create();
+    let base = server.url();
+
+    let started = Instant::now();
+    let stats = LinkScraper { base: base.clone() }
+        .run_with_stats(
+            Opts::new()
+                .with_urls(vec![base.as_str()])
+                .with_max_duration(Duration::from_millis(100))
+                .with_shutdown_grace_period(Duration::from_millis(100)),
+        )
+        .await
+        .unwrap();
+
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(stats.finish_reason, FinishReason::TimeLimit);
+    assert_eq!(stats.pages(), 0);
+}
+
+#[async_std::test]
+async fn test_duration_counts_from_start() {
+    let fetcher = MemoryFetcher::new().with_page("http://example.com/", "page");
+    let opts = Opts::new()
+        .with_urls(vec!["http://example.com/"])
+        .with_max_duration(Duration::from_millis(100));
+    let mut crabler = Crabler::with_fetcher(
+        LinkScraper {
+            base: "http://example.com".to_string(),
+        },
+        &opts,
+        fetcher,
+    );
+
+    async_std::task::sleep(Duration::from_millis(200)).await;
+    let stats = crabler.start(&opts).await.unwrap();
+
+    assert_eq!(stats.finish_reason, FinishReason::Completed);
+    assert_eq!(stats.pages(), 1);
+}
+
+#[async_std::test]
+async fn test_completed_within_limits() {
+    let mut server = new_mock_server().await;
+    let _mocks = mock_site(&mut server, r#"<a href="/page/1">1</a>"#);
+    let base = server.url();
+
+    let stats = LinkScraper { base: base.clone() }
+        .run_with_stats(
+            Opts::new()
+                .with_urls(vec![base.as_str()])
+                .with_max_pages(10)
+                .with_max_duration(Duration::from_secs(60)),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(stats.finish_reason, FinishReason::Completed);
+    assert_eq!(stats.pages(), 2);
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "metrics")]
+
+extern crate crabler;
+
+use crabler::*;
+
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+struct MetricsScraper {
+    metrics_url: String,
+    metrics: Option<String>,
+}
+
+impl MetricsScraper {
+    async fn response_handler(&mut self, _response: Response) -> Result<()> {
+        // idle client must not block the endpoint for others
+        let address = self
+            .metrics_url
+            .trim_start_matches("http://")
+            .trim_end_matches("/metrics");
+        let _idle = async_std::net::TcpStream::connect(address).await?;
+
+        let body = surf::get(&self.metrics_url).recv_string().await?;
+        self.metrics = Some(body);
+        Ok(())
+    }
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+fn free_address() -> String {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+#[async_std::test]
+async fn test_metrics_endpoint() {
+    let mut server = new_mock_server().await;
+    let _index = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_body("hello")
+        .create();
+    let url = server.url();
+    let address = free_address();
+
+    let scraper = MetricsScraper {
+        metrics_url: format!("http://{}/metrics", address),
+        metrics: None,
+    };
+
+    let scraper = scraper
+        .crawl(
+            Opts::new()
+                .with_urls(vec![url.as_str()])
+                .with_threads(2)
+                .with_metrics_endpoint(&address),
+        )
+        .await
+        .unwrap();
+
+    let metrics = scraper.metrics.unwrap();
+    assert!(metrics.contains("crabler_queue_length{queue=\"input\"} 0\n"));
+    assert!(metrics.contains("crabler_in_flight 1\n"));
+    assert!(metrics.contains("crabler_workers 2\n"));
+    assert!(metrics.contains("crabler_requests_total{host=\"127.0.0.1\"} 1\n"));
+    assert!(metrics.contains("crabler_responses_total{status=\"200\"} 1\n"));
+    assert!(metrics.contains("crabler_request_duration_seconds_bucket{le=\"+Inf\"} 1\n"));
+    assert!(metrics.contains("crabler_request_duration_seconds_count 1\n"));
+    assert!(metrics.contains("# TYPE crabler_request_duration_seconds histogram\n"));
+
+    assert!(
+        async_std::net::TcpStream::connect(&address).await.is_err(),
+        "Metrics server is stopped after crawl"
+    );
+}
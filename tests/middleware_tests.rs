@@ -0,0 +1,202 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, Mutex};
+
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct LinkScraper {
+    visited: Vec<(String, u16)>,
+}
+
+impl LinkScraper {
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        self.visited.push((response.url.clone(), response.status));
+        Ok(())
+    }
+
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        response
+            .navigate(format!("http://example.com{}", href))
+            .await
+    }
+}
+
+struct Rules {
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl Middleware for Rules {
+    async fn before_request(&self, request: &mut FetchRequest) -> Result<RequestAction> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("before {}", request.url));
+
+        if request.url.ends_with("/private") {
+            Ok(RequestAction::Drop)
+        } else if request.url.ends_with("/cached") {
+            Ok(RequestAction::Respond(FetchResponse::from_bytes(
+                203,
+                vec![],
+                b"cached".to_vec(),
+            )))
+        } else {
+            request.url = request.url.replace("/old", "/new");
+            Ok(RequestAction::Continue)
+        }
+    }
+
+    async fn after_response(
+        &self,
+        request: &FetchRequest,
+        response: &mut FetchResponse,
+    ) -> Result<ResponseAction> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("after {}", request.url));
+
+        if response.status == 404 {
+            Ok(ResponseAction::Drop)
+        } else {
+            Ok(ResponseAction::Continue)
+        }
+    }
+}
+
+#[async_std::test]
+async fn test_middleware_modifies_short_circuits_and_drops() {
+    let fetcher = MemoryFetcher::new()
+        .with_page(
+            "http://example.com/",
+            r#"<a href="/old">1</a><a href="/private">2</a><a href="/cached">3</a><a href="/missing">4</a>"#,
+        )
+        .with_page("http://example.com/new", "new")
+        .with_page("http://example.com/private", "private");
+    let calls = Arc::new(Mutex::new(vec![]));
+    let opts = Opts::new()
+        .with_urls(vec!["http://example.com/"])
+        .with_threads(1)
+        .with_middleware(Rules {
+            calls: calls.clone(),
+        });
+
+    let mut crabler = Crabler::with_fetcher(LinkScraper { visited: vec![] }, &opts, fetcher);
+    crabler.start(&opts).await.unwrap();
+
+    assert_eq!(
+        crabler.into_scraper().visited,
+        vec![
+            ("http://example.com/".to_string(), 200),
+            ("http://example.com/old".to_string(), 200),
+            ("http://example.com/private".to_string(), 304),
+            ("http://example.com/cached".to_string(), 203),
+            ("http://example.com/missing".to_string(), 304),
+        ],
+        "Rewritten url is fetched, dropped requests and responses are reported as skipped"
+    );
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec![
+            "before http://example.com/",
+            "after http://example.com/",
+            "before http://example.com/old",
+            "after http://example.com/new",
+            "before http://example.com/private",
+            "before http://example.com/cached",
+            "after http://example.com/cached",
+            "before http://example.com/missing",
+            "after http://example.com/missing",
+        ]
+    );
+}
+
+struct Named {
+    name: &'static str,
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl Middleware for Named {
+    async fn before_request(&self, _request: &mut FetchRequest) -> Result<RequestAction> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("before {}", self.name));
+        Ok(RequestAction::Continue)
+    }
+
+    async fn after_response(
+        &self,
+        _request: &FetchRequest,
+        _response: &mut FetchResponse,
+    ) -> Result<ResponseAction> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push(format!("after {}", self.name));
+        Ok(ResponseAction::Continue)
+    }
+}
+
+#[async_std::test]
+async fn test_middleware_order() {
+    let fetcher = MemoryFetcher::new().with_page("http://example.com/", "index");
+    let calls = Arc::new(Mutex::new(vec![]));
+    let opts = Opts::new()
+        .with_urls(vec!["http://example.com/"])
+        .with_middleware(Named {
+            name: "first",
+            calls: calls.clone(),
+        })
+        .with_middleware(Named {
+            name: "second",
+            calls: calls.clone(),
+        });
+
+    let mut crabler = Crabler::with_fetcher(LinkScraper { visited: vec![] }, &opts, fetcher);
+    crabler.start(&opts).await.unwrap();
+
+    assert_eq!(
+        *calls.lock().unwrap(),
+        vec![
+            "before first",
+            "before second",
+            "after second",
+            "after first"
+        ]
+    );
+}
+
+#[async_std::test]
+async fn test_custom_headers() {
+    let mut server = async_std::task::spawn_blocking(mockito::Server::new).await;
+    let url = server.url();
+    let mock = server
+        .mock("GET", "/")
+        .match_header("authorization", "Bearer secret")
+        .match_header("user-agent", "crabler-test")
+        .with_status(200)
+        .with_body("ok")
+        .expect(1)
+        .create_async()
+        .await;
+
+    LinkScraper { visited: vec![] }
+        .run(
+            Opts::new()
+                .with_urls(vec![url.as_str()])
+                .with_follow_redirects(false)
+                .with_header("Authorization", "Bearer secret")
+                .with_header("User-Agent", "ignored")
+                .with_header("user-agent", "crabler-test"),
+        )
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+}
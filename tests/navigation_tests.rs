@@ -0,0 +1,135 @@
+extern crate crabler;
+
+use crabler::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Listing {
+    category: String,
+    position: usize,
+}
+
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct ListingScraper {
+    products: Vec<(String, Option<String>, Listing)>,
+    untagged: Vec<String>,
+}
+
+impl ListingScraper {
+    fn new() -> Self {
+        ListingScraper {
+            products: vec![],
+            untagged: vec![],
+        }
+    }
+
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        let listing = Listing {
+            category: response.url.clone(),
+            position: href.trim_start_matches("/product/").parse().unwrap(),
+        };
+        let navigation = Navigation::new()
+            .with_meta(serde_json::to_value(&listing)?)
+            .with_handler("product");
+
+        response
+            .navigate_with(format!("http://example.com{}", href), navigation)
+            .await
+    }
+
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        match response.handler.as_deref() {
+            Some("product") => {
+                let listing = response.meta_as::<Listing>()?;
+                self.products
+                    .push((response.url.clone(), response.handler.clone(), listing));
+            }
+            _ => {
+                assert_eq!(response.meta, serde_json::Value::Null);
+                self.untagged.push(response.url.clone());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn site() -> MemoryFetcher {
+    MemoryFetcher::new()
+        .with_page(
+            "http://example.com/books",
+            r#"<a href="/product/1">1</a><a href="/product/2">2</a><a href="/product/3">3</a>"#,
+        )
+        .with_page("http://example.com/product/1", "one")
+        .with_page("http://example.com/product/2", "two")
+        .with_page("http://example.com/product/3", "three")
+}
+
+async fn crawl(opts: Opts) -> ListingScraper {
+    let opts = opts.with_urls(vec!["http://example.com/books"]);
+    let mut crabler = Crabler::with_fetcher(ListingScraper::new(), &opts, site());
+    crabler.start(&opts).await.unwrap();
+
+    crabler.into_scraper()
+}
+
+fn expected_products() -> Vec<(String, Option<String>, Listing)> {
+    (1..=3)
+        .map(|position| {
+            (
+                format!("http://example.com/product/{}", position),
+                Some("product".to_string()),
+                Listing {
+                    category: "http://example.com/books".to_string(),
+                    position,
+                },
+            )
+        })
+        .collect()
+}
+
+#[async_std::test]
+async fn test_navigation_meta_and_handler() {
+    let mut scraper = crawl(Opts::new()).await;
+    scraper
+        .products
+        .sort_by_key(|(_, _, listing)| listing.position);
+
+    assert_eq!(scraper.products, expected_products());
+    assert_eq!(scraper.untagged, vec!["http://example.com/books"]);
+}
+
+#[async_std::test]
+async fn test_navigation_survives_spilling() {
+    let path = std::env::temp_dir().join(format!(
+        "crabler-{}-navigation-spill.jsonl",
+        std::process::id()
+    ));
+    let mut scraper = crawl(
+        Opts::new()
+            .with_threads(1)
+            .with_queue_capacity(1)
+            .with_queue_spill(&path.to_string_lossy()),
+    )
+    .await;
+    scraper
+        .products
+        .sort_by_key(|(_, _, listing)| listing.position);
+
+    assert_eq!(scraper.products, expected_products());
+}
+
+#[test]
+fn test_navigation_builder() {
+    let navigation = Navigation::new()
+        .with_meta(json!({ "page": 2 }))
+        .with_handler("listing");
+
+    assert_eq!(navigation.meta["page"], 2);
+    assert_eq!(navigation.handler.as_deref(), Some("listing"));
+    assert_eq!(Navigation::new().meta, serde_json::Value::Null);
+}
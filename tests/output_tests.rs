@@ -0,0 +1,164 @@
+extern crate crabler;
+
+use crabler::*;
+use serde::Serialize;
+use std::path::PathBuf;
+
+#[derive(Serialize)]
+struct Book {
+    title: String,
+    price: f64,
+    in_stock: bool,
+}
+
+#[derive(WebScraper)]
+#[on_html("li.book", book_handler)]
+struct BookScraper {}
+
+impl BookScraper {
+    async fn book_handler(&mut self, mut response: Response, li: Element) -> Result<()> {
+        let book = Book {
+            title: li.text().unwrap_or_default().trim().to_string(),
+            price: li.attr("data-price").unwrap().parse().unwrap(),
+            in_stock: li.attr("data-stock").is_some(),
+        };
+        response.emit(&book).await
+    }
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+fn mock_books(server: &mut mockito::ServerGuard) -> mockito::Mock {
+    server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body(
+            r#"<ul>
+                <li class="book" data-price="12.5" data-stock>Dune</li>
+                <li class="book" data-price="8">Solaris, "the novel"</li>
+            </ul>"#,
+        )
+        .create()
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("crabler-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[async_std::test]
+async fn test_json_lines_output() {
+    let mut server = new_mock_server().await;
+    let _books = mock_books(&mut server);
+    let path = temp_path("books.jsonl");
+    let url = server.url();
+
+    BookScraper {}
+        .run(
+            Opts::new()
+                .with_urls(vec![url.as_str()])
+                .with_output(Output::JsonLines(path.to_string_lossy().to_string())),
+        )
+        .await
+        .unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        contents,
+        concat!(
+            r#"{"title":"Dune","price":12.5,"in_stock":true}"#,
+            "\n",
+            r#"{"title":"Solaris, \"the novel\"","price":8.0,"in_stock":false}"#,
+            "\n",
+        )
+    );
+}
+
+#[async_std::test]
+async fn test_csv_output() {
+    let mut server = new_mock_server().await;
+    let _books = mock_books(&mut server);
+    let path = temp_path("books.csv");
+    let url = server.url();
+
+    BookScraper {}
+        .run(
+            Opts::new()
+                .with_urls(vec![url.as_str()])
+                .with_output(Output::Csv(path.to_string_lossy().to_string())),
+        )
+        .await
+        .unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        contents,
+        "title,price,in_stock\nDune,12.5,true\n\"Solaris, \"\"the novel\"\"\",8.0,false\n"
+    );
+}
+
+#[async_std::test]
+async fn test_no_file_without_items() {
+    let jsonl = temp_path("empty.jsonl");
+    let csv = temp_path("empty.csv");
+    let opts = Opts::new()
+        .with_urls(vec!["http://example.com/"])
+        .with_output(Output::JsonLines(jsonl.to_string_lossy().to_string()))
+        .with_output(Output::Csv(csv.to_string_lossy().to_string()));
+    let fetcher = MemoryFetcher::new().with_page("http://example.com/", "<ul></ul>");
+
+    let mut crabler = Crabler::with_fetcher(BookScraper {}, &opts, fetcher);
+    crabler.start(&opts).await.unwrap();
+
+    assert!(!jsonl.exists());
+    assert!(!csv.exists());
+}
+
+#[cfg(feature = "sqlite")]
+#[async_std::test]
+async fn test_sqlite_output() {
+    let mut server = new_mock_server().await;
+    let _books = mock_books(&mut server);
+    let path = temp_path("books.sqlite");
+    let url = server.url();
+
+    BookScraper {}
+        .run(
+            Opts::new()
+                .with_urls(vec![url.as_str()])
+                .with_output(Output::Sqlite {
+                    path: path.to_string_lossy().to_string(),
+                    table: "books".to_string(),
+                }),
+        )
+        .await
+        .unwrap();
+
+    let connection = rusqlite::Connection::open(&path).unwrap();
+    let mut statement = connection
+        .prepare("SELECT title, price, in_stock FROM books ORDER BY price")
+        .unwrap();
+    let rows = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, f64>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })
+        .unwrap()
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(
+        rows,
+        vec![
+            ("Solaris, \"the novel\"".to_string(), 8.0, 0),
+            ("Dune".to_string(), 12.5, 1),
+        ]
+    );
+}
@@ -0,0 +1,92 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[derive(WebScraper)]
+#[on_page(page_handler)]
+#[on_page(response_handler)]
+#[on_page(body_handler)]
+struct PageScraper {
+    pages: Vec<(String, u16, Option<String>, Vec<String>)>,
+    responses: Vec<String>,
+    bodies: Vec<String>,
+}
+
+impl PageScraper {
+    fn new() -> Self {
+        PageScraper {
+            pages: vec![],
+            responses: vec![],
+            bodies: vec![],
+        }
+    }
+
+    async fn page_handler(&mut self, mut page: Page) -> Result<()> {
+        let links = page
+            .document()
+            .select("a[href]")
+            .iter()
+            .map(|a| a.attr("href").unwrap())
+            .collect::<Vec<_>>();
+
+        self.pages.push((
+            page.url.clone(),
+            page.status,
+            page.header("x-shelf").map(|shelf| shelf.to_string()),
+            links.clone(),
+        ));
+
+        for href in links {
+            page.navigate(format!("http://example.com{}", href)).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        self.responses.push(response.url);
+        Ok(())
+    }
+
+    async fn body_handler(&mut self, page: String) -> Result<()> {
+        self.bodies.push(page);
+        Ok(())
+    }
+}
+
+#[async_std::test]
+async fn test_on_page_handler_arguments() {
+    let fetcher = MemoryFetcher::new()
+        .with_response(
+            "http://example.com/",
+            200,
+            vec![("Content-Type", "text/html"), ("X-Shelf", "fiction")],
+            br#"<a href="/one">1</a>"#.to_vec(),
+        )
+        .with_page("http://example.com/one", "one");
+    let opts = Opts::new()
+        .with_urls(vec!["http://example.com/"])
+        .with_threads(1);
+
+    let mut crabler = Crabler::with_fetcher(PageScraper::new(), &opts, fetcher);
+    crabler.start(&opts).await.unwrap();
+    let scraper = crabler.into_scraper();
+
+    assert_eq!(
+        scraper.pages,
+        vec![
+            (
+                "http://example.com/".to_string(),
+                200,
+                Some("fiction".to_string()),
+                vec!["/one".to_string()]
+            ),
+            ("http://example.com/one".to_string(), 200, None, vec![]),
+        ]
+    );
+    assert_eq!(
+        scraper.responses,
+        vec!["http://example.com/", "http://example.com/one"]
+    );
+    assert_eq!(scraper.bodies, vec![r#"<a href="/one">1</a>"#, "one"]);
+}
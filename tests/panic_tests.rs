@@ -0,0 +1,97 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+#[on_html("p.explode", panicking_handler)]
+#[on_error(error_handler)]
+struct PanickingScraper {
+    statuses: Vec<u16>,
+    errors: Vec<(&'static str, String)>,
+}
+
+impl PanickingScraper {
+    fn new() -> Self {
+        PanickingScraper {
+            statuses: vec![],
+            errors: vec![],
+        }
+    }
+
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        self.statuses.push(response.status);
+        Ok(())
+    }
+
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        response
+            .navigate(format!("http://example.com{}", href))
+            .await
+    }
+
+    async fn panicking_handler(&mut self, response: Response, _p: Element) -> Result<()> {
+        panic!("boom on {}", response.url);
+    }
+
+    async fn error_handler(&mut self, context: ErrorContext, error: CrablerError) -> Result<()> {
+        self.errors.push((context.hook, error.to_string()));
+        Ok(())
+    }
+}
+
+fn site() -> MemoryFetcher {
+    MemoryFetcher::new()
+        .with_page(
+            "http://example.com/",
+            r#"<p class="explode">x</p><a href="/one">1</a>"#,
+        )
+        .with_page("http://example.com/one", "one")
+}
+
+async fn crawl(opts: Opts) -> (Result<CrawlStats>, PanickingScraper) {
+    let opts = opts.with_urls(vec!["http://example.com/"]);
+    let mut crabler = Crabler::with_fetcher(PanickingScraper::new(), &opts, site());
+    let result = crabler.start(&opts).await;
+
+    (result, crabler.into_scraper())
+}
+
+#[async_std::test]
+async fn test_panic_aborts_crawl_with_error() {
+    let (result, _) = crawl(Opts::new()).await;
+
+    match result {
+        Err(CrablerError::HandlerPanicked { url, payload }) => {
+            assert_eq!(url, "http://example.com/");
+            assert_eq!(payload, "boom on http://example.com/");
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[async_std::test]
+async fn test_panic_is_logged_and_crawl_continues() {
+    let (result, scraper) = crawl(Opts::new().with_error_policy(ErrorPolicy::LogAndContinue)).await;
+    let stats = result.unwrap();
+
+    assert_eq!(scraper.statuses, vec![200, 200]);
+    assert_eq!(stats.errors.get("HandlerPanicked"), Some(&1));
+    assert_eq!(stats.finish_reason, FinishReason::Completed);
+}
+
+#[async_std::test]
+async fn test_panic_is_routed_to_error_handler() {
+    let (result, scraper) = crawl(Opts::new().with_error_policy(ErrorPolicy::Handler)).await;
+    result.unwrap();
+
+    assert_eq!(
+        scraper.errors,
+        vec![(
+            "on_html",
+            "handler panicked on http://example.com/: boom on http://example.com/".to_string()
+        )]
+    );
+}
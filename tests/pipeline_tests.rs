@@ -0,0 +1,158 @@
+extern crate crabler;
+
+use crabler::*;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+#[derive(Serialize)]
+struct Link {
+    href: String,
+    text: String,
+}
+
+#[derive(WebScraper)]
+#[on_html("a", link_handler)]
+struct LinkScraper {}
+
+impl LinkScraper {
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let link = Link {
+            href: a.attr("href").unwrap_or_default(),
+            text: a.text().unwrap_or_default(),
+        };
+        response.emit(&link).await
+    }
+}
+
+#[derive(Default)]
+struct Dedupe {
+    seen: HashSet<String>,
+}
+
+#[async_trait(?Send)]
+impl ItemProcessor for Dedupe {
+    async fn process(&mut self, item: Item) -> Result<Option<Item>> {
+        let href = item["href"].as_str().unwrap_or_default().to_string();
+
+        if self.seen.insert(href) {
+            Ok(Some(item))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+struct CollectSink {
+    items: Arc<Mutex<Vec<Item>>>,
+    flushed: Arc<Mutex<bool>>,
+}
+
+#[async_trait(?Send)]
+impl ItemSink for CollectSink {
+    async fn write(&mut self, item: Item) -> Result<()> {
+        self.items.lock().unwrap().push(item);
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<()> {
+        *self.flushed.lock().unwrap() = true;
+        Ok(())
+    }
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+#[async_std::test]
+async fn test_items_pass_through_pipeline() {
+    let mut server = new_mock_server().await;
+
+    let _page = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body(
+            r#"<html><body>
+                <a href="/first">First</a>
+                <a>No href</a>
+                <a href="/second">Second</a>
+                <a href="/first">First again</a>
+            </body></html>"#,
+        )
+        .create();
+
+    let items = Arc::new(Mutex::new(vec![]));
+    let flushed = Arc::new(Mutex::new(false));
+    let sink = CollectSink {
+        items: items.clone(),
+        flushed: flushed.clone(),
+    };
+
+    let url = server.url();
+
+    LinkScraper {}
+        .run(
+            Opts::new()
+                .with_urls(vec![url.as_str()])
+                // validation
+                .with_item_processor(|item: Item| {
+                    if item["href"].as_str().unwrap_or_default().is_empty() {
+                        Ok(None)
+                    } else {
+                        Ok(Some(item))
+                    }
+                })
+                .with_item_processor(Dedupe::default())
+                // enrichment
+                .with_item_processor(|mut item: Item| {
+                    item["source"] = "pipeline_tests".into();
+                    Ok(Some(item))
+                })
+                .with_item_sink(sink),
+        )
+        .await
+        .unwrap();
+
+    let items = items.lock().unwrap();
+    let hrefs = items
+        .iter()
+        .map(|item| item["href"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(hrefs, vec!["/first", "/second"]);
+    assert_eq!(items[0]["text"], "First");
+    assert!(items.iter().all(|item| item["source"] == "pipeline_tests"));
+    assert!(*flushed.lock().unwrap(), "Sinks are flushed after crawl");
+}
+
+#[async_std::test]
+async fn test_pipeline_errors_follow_error_policy() {
+    let fetcher = MemoryFetcher::new().with_page(
+        "http://example.com/",
+        r#"<a href="/first">First</a><a>No href</a><a href="/second">Second</a>"#,
+    );
+    let items = Arc::new(Mutex::new(vec![]));
+    let sink = CollectSink {
+        items: items.clone(),
+        flushed: Arc::new(Mutex::new(false)),
+    };
+    let opts = Opts::new()
+        .with_urls(vec!["http://example.com/"])
+        .with_error_policy(ErrorPolicy::LogAndContinue)
+        .with_item_processor(|item: Item| {
+            if item["href"].as_str().unwrap_or_default().is_empty() {
+                Err(CrablerError::Output("missing href".to_string()))
+            } else {
+                Ok(Some(item))
+            }
+        })
+        .with_item_sink(sink);
+
+    let mut crabler = Crabler::with_fetcher(LinkScraper {}, &opts, fetcher);
+    let stats = crabler.start(&opts).await.unwrap();
+
+    let items = items.lock().unwrap();
+    assert_eq!(items.len(), 2, "Invalid item is dropped, crawl goes on");
+    assert_eq!(stats.errors.get("Output"), Some(&1));
+}
@@ -0,0 +1,198 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, Mutex};
+
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct QueueScraper {
+    base: String,
+    statuses: Vec<u16>,
+    max_queue_len: usize,
+}
+
+impl QueueScraper {
+    fn new(base: &str) -> Self {
+        QueueScraper {
+            base: base.to_string(),
+            statuses: vec![],
+            max_queue_len: 0,
+        }
+    }
+
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        self.statuses.push(response.status);
+        Ok(())
+    }
+
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        response.navigate(format!("{}{}", self.base, href)).await?;
+        self.max_queue_len = self.max_queue_len.max(response.queue_len());
+
+        Ok(())
+    }
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+fn mock_site(server: &mut mockito::ServerGuard, links: usize) -> Vec<mockito::Mock> {
+    let index = (0..links)
+        .map(|i| format!(r#"<a href="/page/{}">{}</a>"#, i, i))
+        .collect::<String>();
+
+    vec![
+        server
+            .mock("GET", "/")
+            .with_status(200)
+            .with_body(index)
+            .create(),
+        server
+            .mock("GET", mockito::Matcher::Regex("^/page/\\d+$".to_string()))
+            .with_status(200)
+            .with_body(r#"<a href="/">home</a>"#)
+            .create(),
+    ]
+}
+
+#[async_std::test]
+async fn test_bounded_queue_applies_backpressure() {
+    let mut server = new_mock_server().await;
+    let _mocks = mock_site(&mut server, 50);
+    let base = server.url();
+    let index = format!("{}/", base);
+
+    let scraper = QueueScraper::new(&base)
+        .crawl(
+            Opts::new()
+                .with_urls(vec![index.as_str()])
+                .with_threads(2)
+                .with_queue_capacity(2),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(scraper.statuses.len(), 51);
+    assert!(scraper.statuses.iter().all(|status| *status == 200));
+    assert!(scraper.max_queue_len > 0);
+    assert!(
+        scraper.max_queue_len <= 2,
+        "Queue grew to {} entries",
+        scraper.max_queue_len
+    );
+}
+
+#[async_std::test]
+async fn test_bounded_queue_spills_to_disk() {
+    let mut server = new_mock_server().await;
+    let _mocks = mock_site(&mut server, 50);
+    let base = server.url();
+    let index = format!("{}/", base);
+    let path = std::env::temp_dir().join(format!("crabler-{}-spill.jsonl", std::process::id()));
+
+    let scraper = QueueScraper::new(&base)
+        .crawl(
+            Opts::new()
+                .with_urls(vec![index.as_str()])
+                .with_queue_capacity(2)
+                .with_queue_spill(&path.to_string_lossy()),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(scraper.statuses.len(), 51);
+    assert!(scraper.statuses.iter().all(|status| *status == 200));
+    // only 2 requests fit in memory, queue can outgrow it only by spilling to disk
+    assert!(
+        scraper.max_queue_len > 2,
+        "Queue did not spill, max len {}",
+        scraper.max_queue_len
+    );
+    assert!(!path.exists(), "Spill file is removed after crawl");
+}
+
+#[async_std::test]
+async fn test_visited_urls_are_not_queued() {
+    let mut server = new_mock_server().await;
+    let _mocks = mock_site(&mut server, 3);
+    let base = server.url();
+    let index = format!("{}/", base);
+    let opts = Opts::new().with_urls(vec![index.as_str()]);
+
+    let mut crabler = Crabler::new(QueueScraper::new(&base), &opts);
+    let stats = crabler.start(&opts).await.unwrap();
+
+    // every page links back to already visited index
+    assert_eq!(stats.duplicates, 3);
+    assert_eq!(
+        crabler.into_scraper().statuses,
+        vec![200, 200, 200, 200],
+        "Skipped urls never reach workers"
+    );
+}
+
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct SharedLinkScraper {
+    queue_len_after_index: Option<usize>,
+}
+
+impl SharedLinkScraper {
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        if response.url == "http://example.com/" {
+            self.queue_len_after_index = Some(response.queue_len());
+        }
+        Ok(())
+    }
+
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        response
+            .navigate(format!("http://example.com{}", href))
+            .await
+    }
+}
+
+struct CountRequests {
+    urls: Arc<Mutex<Vec<String>>>,
+}
+
+#[async_trait]
+impl Middleware for CountRequests {
+    async fn before_request(&self, request: &mut FetchRequest) -> Result<RequestAction> {
+        self.urls.lock().unwrap().push(request.url.clone());
+        Ok(RequestAction::Continue)
+    }
+}
+
+#[async_std::test]
+async fn test_url_linked_many_times_is_queued_once() {
+    let index = (0..20)
+        .map(|i| format!(r#"<a href="/shared">{}</a>"#, i))
+        .collect::<String>();
+    let fetcher = MemoryFetcher::new()
+        .with_page("http://example.com/", &index)
+        .with_page("http://example.com/shared", "shared");
+    let urls = Arc::new(Mutex::new(vec![]));
+    let opts = Opts::new()
+        .with_urls(vec!["http://example.com/"])
+        .with_middleware(CountRequests { urls: urls.clone() });
+
+    let scraper = SharedLinkScraper {
+        queue_len_after_index: None,
+    };
+    let mut crabler = Crabler::with_fetcher(scraper, &opts, fetcher);
+    let stats = crabler.start(&opts).await.unwrap();
+
+    let queue_len = crabler.into_scraper().queue_len_after_index.unwrap();
+    assert!(queue_len <= 1, "Shared url queued {} times", queue_len);
+    assert_eq!(stats.duplicates, 19);
+    assert_eq!(
+        *urls.lock().unwrap(),
+        vec!["http://example.com/", "http://example.com/shared"]
+    );
+}
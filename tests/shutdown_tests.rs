@@ -0,0 +1,123 @@
+extern crate crabler;
+
+use crabler::*;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+#[derive(Serialize)]
+struct Link {
+    href: String,
+}
+
+#[derive(WebScraper)]
+#[on_html("a[href]", link_handler)]
+struct CancellingScraper {
+    base: String,
+    cancellation: CancellationHandle,
+}
+
+impl CancellingScraper {
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        response.emit(&Link { href: href.clone() }).await?;
+
+        // stop right after first link is scheduled
+        response.navigate(format!("{}{}", self.base, href)).await?;
+        self.cancellation.cancel();
+
+        Ok(())
+    }
+}
+
+#[derive(WebScraper)]
+struct IdleScraper {}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+#[async_std::test]
+async fn test_cancel_stops_queuing_and_flushes_output() {
+    let mut server = new_mock_server().await;
+    let base = server.url();
+    let _index = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_body(r#"<a href="/one">1</a><a href="/two">2</a><a href="/three">3</a>"#)
+        .create();
+    let _pages = server
+        .mock(
+            "GET",
+            mockito::Matcher::Regex("^/(one|two|three)$".to_string()),
+        )
+        .with_status(200)
+        .with_body("page")
+        .create();
+
+    let path = std::env::temp_dir().join(format!("crabler-{}-links.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let cancellation = CancellationHandle::new();
+    let scraper = CancellingScraper {
+        base: base.clone(),
+        cancellation: cancellation.clone(),
+    };
+
+    let stats = scraper
+        .run_with_stats(
+            Opts::new()
+                .with_urls(vec![base.as_str()])
+                .with_cancellation_handle(cancellation)
+                .with_output(Output::JsonLines(path.to_string_lossy().to_string())),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(stats.finish_reason, FinishReason::Cancelled);
+    assert!(
+        stats.pages() <= 2,
+        "Only already queued page may be fetched"
+    );
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(
+        contents.lines().collect::<Vec<_>>(),
+        vec![
+            r#"{"href":"/one"}"#,
+            r#"{"href":"/two"}"#,
+            r#"{"href":"/three"}"#
+        ],
+        "Items emitted before cancellation are flushed"
+    );
+}
+
+#[async_std::test]
+async fn test_cancel_aborts_slow_requests_after_grace_period() {
+    let mut server = new_mock_server().await;
+    let _slow = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_body_from_request(|_| {
+            std::thread::sleep(Duration::from_secs(3));
+            b"slow".to_vec()
+        })
+        .create();
+    let url = server.url();
+
+    let opts = Opts::new()
+        .with_urls(vec![url.as_str()])
+        .with_shutdown_grace_period(Duration::from_millis(100));
+    let mut crabler = Crabler::new(IdleScraper {}, &opts);
+
+    let cancellation = crabler.cancellation_handle();
+    async_std::task::spawn(async move {
+        async_std::task::sleep(Duration::from_millis(100)).await;
+        cancellation.cancel();
+    });
+
+    let started = Instant::now();
+    let stats = crabler.start(&opts).await.unwrap();
+
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(stats.finish_reason, FinishReason::Cancelled);
+    assert_eq!(stats.pages(), 0);
+}
@@ -0,0 +1,225 @@
+extern crate crabler;
+
+use crabler::*;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use std::sync::{Arc, RwLock};
+
+type Seen = Arc<RwLock<Vec<String>>>;
+
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+#[on_sitemap_entry(sitemap_entry_handler)]
+struct SitemapScraper {
+    visited: Seen,
+    entries_seen: Seen,
+}
+
+impl SitemapScraper {
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        if response.status == 200 {
+            self.visited.write().unwrap().push(response.url);
+        }
+        Ok(())
+    }
+
+    async fn sitemap_entry_handler(&mut self, entry: &SitemapEntry) -> Result<bool> {
+        self.entries_seen.write().unwrap().push(entry.loc.clone());
+        Ok(!entry.loc.ends_with("/skip"))
+    }
+}
+
+fn make_scraper() -> (SitemapScraper, Seen, Seen) {
+    let visited = Arc::new(RwLock::new(vec![]));
+    let entries_seen = Arc::new(RwLock::new(vec![]));
+    let scraper = SitemapScraper {
+        visited: visited.clone(),
+        entries_seen: entries_seen.clone(),
+    };
+    (scraper, visited, entries_seen)
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+fn gzip(body: &str) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(body.as_bytes()).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn mock_page(server: &mut mockito::ServerGuard, path: &str) -> mockito::Mock {
+    server
+        .mock("GET", path)
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<html><body></body></html>")
+}
+
+#[async_std::test]
+async fn test_sitemap_discovered_from_robots() {
+    let mut server = new_mock_server().await;
+    let base = server.url();
+
+    let _robots = server
+        .mock("GET", "/robots.txt")
+        .with_status(200)
+        .with_body(format!(
+            "User-agent: *\nDisallow: /private\n\nSitemap: {}/sitemap_index.xml\n",
+            base
+        ))
+        .create();
+
+    let _index = server
+        .mock("GET", "/sitemap_index.xml")
+        .with_status(200)
+        .with_header("content-type", "application/xml")
+        .with_body(format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+                <sitemap><loc>{0}/sitemap-pages.xml.gz</loc><lastmod>2024-05-01</lastmod></sitemap>
+                <sitemap><loc>{0}/sitemap-archive.xml</loc><lastmod>2001-01-01</lastmod></sitemap>
+            </sitemapindex>"#,
+            base
+        ))
+        .create();
+
+    let _pages = server
+        .mock("GET", "/sitemap-pages.xml.gz")
+        .with_status(200)
+        .with_header("content-type", "application/gzip")
+        .with_body(gzip(&format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+                    xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+                <url>
+                    <loc>{0}/fresh</loc>
+                    <lastmod>2024-04-30T10:00:00+00:00</lastmod>
+                    <image:image><image:loc>{0}/image.png</image:loc></image:image>
+                </url>
+                <url><loc>{0}/stale</loc><lastmod>2010-01-01</lastmod></url>
+                <url><loc>{0}/skip</loc></url>
+            </urlset>"#,
+            base
+        )))
+        .create();
+
+    let archive = server
+        .mock("GET", "/sitemap-archive.xml")
+        .with_status(200)
+        .with_body("<urlset></urlset>")
+        .expect(0)
+        .create();
+
+    let _root = mock_page(&mut server, "/").create();
+    let _fresh = mock_page(&mut server, "/fresh").create();
+    let stale = mock_page(&mut server, "/stale").expect(0).create();
+    let skip = mock_page(&mut server, "/skip").expect(0).create();
+
+    let (scraper, visited, entries_seen) = make_scraper();
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![base.as_str()])
+                .with_sitemap_discovery(true)
+                .with_sitemap_modified_since("2020-01-01T00:00:00Z".parse().unwrap()),
+        )
+        .await
+        .unwrap();
+
+    let visited = visited.read().unwrap();
+    assert!(visited.contains(&format!("{}/fresh", base)));
+    assert!(!visited.contains(&format!("{}/stale", base)));
+    assert!(!visited.contains(&format!("{}/skip", base)));
+
+    let entries_seen = entries_seen.read().unwrap();
+    assert_eq!(
+        *entries_seen,
+        vec![format!("{}/fresh", base), format!("{}/skip", base)],
+        "Stale entries and nested image tags should not reach the hook"
+    );
+
+    archive.assert();
+    stale.assert();
+    skip.assert();
+}
+
+#[async_std::test]
+async fn test_sitemap_well_known_fallback() {
+    let mut server = new_mock_server().await;
+    let base = server.url();
+
+    let _robots = server.mock("GET", "/robots.txt").with_status(404).create();
+
+    let _sitemap = server
+        .mock("GET", "/sitemap.xml")
+        .with_status(200)
+        .with_body(format!(
+            r#"<urlset><url><loc><![CDATA[{}/from-sitemap]]></loc></url></urlset>"#,
+            base
+        ))
+        .create();
+
+    let _root = mock_page(&mut server, "/").create();
+    let _page = mock_page(&mut server, "/from-sitemap").create();
+
+    let (scraper, visited, _entries_seen) = make_scraper();
+
+    scraper
+        .run(
+            Opts::new()
+                .with_urls(vec![base.as_str()])
+                .with_sitemap_discovery(true)
+                .with_threads(2),
+        )
+        .await
+        .unwrap();
+
+    assert!(visited
+        .read()
+        .unwrap()
+        .contains(&format!("{}/from-sitemap", base)));
+    assert!(
+        !visited
+            .read()
+            .unwrap()
+            .contains(&format!("{}/robots.txt", base)),
+        "Missing robots.txt should not be reported as success"
+    );
+}
+
+#[async_std::test]
+async fn test_explicit_sitemap_seed() {
+    let mut server = new_mock_server().await;
+    let base = server.url();
+
+    let _sitemap = server
+        .mock("GET", "/products.xml")
+        .with_status(200)
+        .with_body(format!(
+            r#"<urlset>
+                <url><loc>{0}/product/1</loc><priority>0.8</priority></url>
+                <url><loc>{0}/product/2</loc><changefreq>daily</changefreq></url>
+            </urlset>"#,
+            base
+        ))
+        .create();
+
+    let _first = mock_page(&mut server, "/product/1").create();
+    let _second = mock_page(&mut server, "/product/2").create();
+
+    let (scraper, visited, _entries_seen) = make_scraper();
+    let sitemap_url = format!("{}/products.xml", base);
+
+    scraper
+        .run(Opts::new().with_sitemaps(vec![sitemap_url.as_str()]))
+        .await
+        .unwrap();
+
+    let visited = visited.read().unwrap();
+    assert!(visited.contains(&format!("{}/product/1", base)));
+    assert!(visited.contains(&format!("{}/product/2", base)));
+}
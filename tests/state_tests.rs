@@ -0,0 +1,79 @@
+extern crate crabler;
+
+use crabler::*;
+
+// Plain fields, no Arc<RwLock<...>>: state is handed back by crawl()
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct StatefulScraper {
+    statuses: Vec<u16>,
+    links: Vec<String>,
+}
+
+impl StatefulScraper {
+    fn new() -> Self {
+        StatefulScraper {
+            statuses: vec![],
+            links: vec![],
+        }
+    }
+
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        self.statuses.push(response.status);
+        Ok(())
+    }
+
+    async fn link_handler(&mut self, _response: Response, a: Element) -> Result<()> {
+        if let Some(href) = a.attr("href") {
+            self.links.push(href);
+        }
+        Ok(())
+    }
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+fn mock_index(server: &mut mockito::ServerGuard) -> mockito::Mock {
+    server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body(r#"<a href="/one">1</a><a href="/two">2</a>"#)
+        .create()
+}
+
+#[async_std::test]
+async fn test_crawl_returns_scraper() {
+    let mut server = new_mock_server().await;
+    let _index = mock_index(&mut server);
+    let url = server.url();
+
+    let scraper = StatefulScraper::new()
+        .crawl(Opts::new().with_urls(vec![url.as_str()]))
+        .await
+        .unwrap();
+
+    assert_eq!(scraper.statuses, vec![200]);
+    assert_eq!(scraper.links, vec!["/one", "/two"]);
+}
+
+#[async_std::test]
+async fn test_crabler_scraper_accessors() {
+    let mut server = new_mock_server().await;
+    let _index = mock_index(&mut server);
+    let url = server.url();
+    let opts = Opts::new().with_urls(vec![url.as_str()]);
+
+    let mut crabler = Crabler::new(StatefulScraper::new(), &opts);
+    crabler.start(&opts).await.unwrap();
+
+    assert_eq!(crabler.scraper().links.len(), 2);
+    crabler.scraper_mut().links.clear();
+
+    let scraper = crabler.into_scraper();
+    assert!(scraper.links.is_empty());
+    assert_eq!(scraper.statuses, vec![200]);
+}
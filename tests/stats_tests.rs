@@ -0,0 +1,94 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, RwLock};
+
+#[derive(WebScraper)]
+#[on_response(response_handler)]
+#[on_html("a[href]", link_handler)]
+struct StatsScraper {
+    base: String,
+    snapshots: Arc<RwLock<Vec<usize>>>,
+}
+
+impl StatsScraper {
+    async fn response_handler(&mut self, response: Response) -> Result<()> {
+        self.snapshots
+            .write()
+            .unwrap()
+            .push(response.stats().pages());
+        Ok(())
+    }
+
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        let url = if href.starts_with("http") {
+            href
+        } else {
+            format!("{}{}", self.base, href)
+        };
+
+        response.navigate(url).await
+    }
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+#[async_std::test]
+async fn test_crawl_stats() {
+    let mut server = new_mock_server().await;
+    let base = server.url();
+
+    let index = r#"<a href="/page">page</a>
+                   <a href="/page">same page</a>
+                   <a href="/missing">missing</a>
+                   <a href="http://127.0.0.1:1/">unreachable</a>"#;
+    let _index = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_body(index)
+        .create();
+    let _page = server
+        .mock("GET", "/page")
+        .with_status(200)
+        .with_body("page")
+        .create();
+    let _missing = server
+        .mock("GET", "/missing")
+        .with_status(404)
+        .with_body("gone")
+        .create();
+
+    let snapshots = Arc::new(RwLock::new(vec![]));
+    let scraper = StatsScraper {
+        base: base.clone(),
+        snapshots: snapshots.clone(),
+    };
+
+    let stats = scraper
+        .run_with_stats(Opts::new().with_urls(vec![base.as_str()]))
+        .await
+        .unwrap();
+
+    assert_eq!(stats.statuses.get(&200), Some(&2));
+    assert_eq!(stats.statuses.get(&404), Some(&1));
+    assert_eq!(stats.pages(), 3);
+    assert_eq!(stats.status_class(2), 2);
+    assert_eq!(stats.status_class(4), 1);
+    assert_eq!(stats.duplicates, 1);
+    assert_eq!(stats.errors.get("SurfError"), Some(&1));
+    assert_eq!(stats.failures(), 1);
+    assert_eq!(
+        stats.bytes,
+        (index.len() + "page".len() + "gone".len()) as u64
+    );
+    assert_eq!(stats.latency.count(), 3);
+    assert_eq!(stats.latency.buckets.len(), LATENCY_BUCKETS.len() + 1);
+    assert!(stats.latency.mean().is_some());
+
+    let snapshots = snapshots.read().unwrap();
+    assert_eq!(snapshots.first(), Some(&1), "Snapshot is taken during run");
+    assert_eq!(snapshots.last(), Some(&3));
+}
@@ -0,0 +1,99 @@
+extern crate crabler;
+
+use crabler::*;
+use std::sync::{Arc, RwLock};
+
+#[derive(WebScraper)]
+#[on_structured_data(structured_data_handler)]
+struct ProductScraper {
+    data: Arc<RwLock<Vec<StructuredData>>>,
+}
+
+impl ProductScraper {
+    async fn structured_data_handler(
+        &mut self,
+        _response: Response,
+        data: StructuredData,
+    ) -> Result<()> {
+        self.data.write().unwrap().push(data);
+        Ok(())
+    }
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+#[async_std::test]
+async fn test_structured_data_extracted() {
+    let mut server = new_mock_server().await;
+
+    let _product = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body(
+            r#"<html>
+              <head>
+                <meta property="og:title" content="Executive Anvil">
+                <meta property="og:image" content="/anvil.png">
+                <meta name="twitter:card" content="summary">
+                <meta name="description" content="not structured">
+                <script type="application/ld+json">
+                  {"@context": "https://schema.org", "@type": "Product", "name": "Executive Anvil"}
+                </script>
+                <script type="application/ld+json">
+                  [{"@type": "BreadcrumbList"}, {"@type": "Organization"}]
+                </script>
+                <script type="application/ld+json">{ not json</script>
+              </head>
+              <body>
+                <div itemscope itemtype="https://schema.org/Product">
+                  <span itemprop="name">Executive Anvil</span>
+                  <img itemprop="image" src="/anvil.png">
+                  <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+                    <meta itemprop="priceCurrency" content="USD">
+                    <span itemprop="price">119.99</span>
+                  </div>
+                </div>
+              </body>
+            </html>"#,
+        )
+        .create();
+
+    let data = Arc::new(RwLock::new(vec![]));
+    let scraper = ProductScraper { data: data.clone() };
+    let url = server.url();
+
+    scraper
+        .run(Opts::new().with_urls(vec![url.as_str()]))
+        .await
+        .unwrap();
+
+    let data = data.read().unwrap();
+    assert_eq!(data.len(), 1);
+    let data = &data[0];
+
+    let types = data
+        .json_ld
+        .iter()
+        .map(|value| value["@type"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(types, vec!["Product", "BreadcrumbList", "Organization"]);
+
+    assert_eq!(data.opengraph_property("og:title"), Some("Executive Anvil"));
+    assert_eq!(data.opengraph_property("og:image"), Some("/anvil.png"));
+    assert_eq!(data.twitter_property("twitter:card"), Some("summary"));
+    assert_eq!(data.opengraph.len() + data.twitter.len(), 3);
+
+    assert_eq!(data.microdata.len(), 1, "Nested items are not top level");
+    let product = &data.microdata[0];
+    assert_eq!(product.item_type, vec!["https://schema.org/Product"]);
+    assert_eq!(product.property("name"), Some("Executive Anvil"));
+    assert_eq!(product.property("image"), Some("/anvil.png"));
+    assert_eq!(product.property("price"), None);
+
+    let offer = product.properties["offers"][0].as_item().unwrap();
+    assert_eq!(offer.property("priceCurrency"), Some("USD"));
+    assert_eq!(offer.property("price"), Some("119.99"));
+}
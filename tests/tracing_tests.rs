@@ -0,0 +1,171 @@
+extern crate crabler;
+
+use crabler::*;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+#[derive(Default)]
+struct SpanData {
+    name: &'static str,
+    parent: Option<u64>,
+    fields: HashMap<String, String>,
+}
+
+struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+impl Visit for FieldVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(
+            field.name().to_string(),
+            format!("{:?}", value).replace('"', ""),
+        );
+    }
+}
+
+/// Collects every span with its fields, enough to inspect the span tree
+#[derive(Clone, Default)]
+struct Collector {
+    next_id: Arc<AtomicU64>,
+    spans: Arc<Mutex<HashMap<u64, SpanData>>>,
+    current: Arc<Mutex<HashMap<std::thread::ThreadId, Vec<u64>>>>,
+}
+
+impl Subscriber for Collector {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attrs: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst) + 1;
+        let parent = match attrs.parent() {
+            Some(parent) => Some(parent.into_u64()),
+            None if attrs.is_contextual() => self
+                .current
+                .lock()
+                .unwrap()
+                .get(&std::thread::current().id())
+                .and_then(|stack| stack.last().copied()),
+            None => None,
+        };
+
+        let mut data = SpanData {
+            name: attrs.metadata().name(),
+            parent,
+            ..Default::default()
+        };
+        attrs.record(&mut FieldVisitor(&mut data.fields));
+        self.spans.lock().unwrap().insert(id, data);
+
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        if let Some(data) = self.spans.lock().unwrap().get_mut(&span.into_u64()) {
+            values.record(&mut FieldVisitor(&mut data.fields));
+        }
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, _: &Event<'_>) {}
+
+    fn enter(&self, span: &Id) {
+        self.current
+            .lock()
+            .unwrap()
+            .entry(std::thread::current().id())
+            .or_default()
+            .push(span.into_u64());
+    }
+
+    fn exit(&self, _: &Id) {
+        if let Some(stack) = self
+            .current
+            .lock()
+            .unwrap()
+            .get_mut(&std::thread::current().id())
+        {
+            stack.pop();
+        }
+    }
+}
+
+#[derive(WebScraper)]
+#[on_html("a[href]", link_handler)]
+struct TracedScraper {
+    base: String,
+}
+
+impl TracedScraper {
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        response.navigate(format!("{}{}", self.base, href)).await
+    }
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+#[async_std::test]
+async fn test_spans_per_work_input() {
+    let collector = Collector::default();
+    tracing::subscriber::set_global_default(collector.clone()).unwrap();
+
+    let mut server = new_mock_server().await;
+    let base = server.url();
+    let _index = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_body(r#"<a href="/page">page</a>"#)
+        .create();
+    let _page = server
+        .mock("GET", "/page")
+        .with_status(404)
+        .with_body("gone")
+        .create();
+
+    TracedScraper { base: base.clone() }
+        .run(Opts::new().with_urls(vec![base.as_str()]))
+        .await
+        .unwrap();
+
+    let spans = collector.spans.lock().unwrap();
+    let work = |url: String| {
+        spans
+            .iter()
+            .find(|(_, span)| span.name == "work" && span.fields.get("url") == Some(&url))
+            .map(|(id, span)| (*id, span))
+            .unwrap()
+    };
+    let children = |parent: u64| {
+        spans
+            .values()
+            .filter(|span| span.parent == Some(parent))
+            .map(|span| {
+                let handler = span.fields.get("handler").cloned().unwrap_or_default();
+                format!("{} {}", span.name, handler).trim().to_string()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let (index_id, index) = work(base.clone());
+    assert_eq!(index.fields["depth"], "0");
+    assert_eq!(index.fields["worker"], "0");
+    assert_eq!(index.fields["attempt"], "1");
+    assert_eq!(index.fields["status"], "200");
+
+    let index_children = children(index_id);
+    assert!(index_children.contains(&"fetch".to_string()));
+    assert!(index_children.contains(&"parse".to_string()));
+    assert!(index_children.contains(&"dispatch on_html".to_string()));
+    assert!(index_children.contains(&"dispatch on_response".to_string()));
+
+    let (_, page) = work(format!("{}/page", base));
+    assert_eq!(page.fields["depth"], "1");
+    assert_eq!(page.fields["status"], "404");
+}
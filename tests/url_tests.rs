@@ -0,0 +1,129 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[derive(WebScraper)]
+#[on_url("/category/*", category_handler)]
+#[on_url("^.*/product/\\d+$", product_handler)]
+#[on_html("a[href]", link_handler, url = "/category/**")]
+#[on_html("h1", product_title_handler, url = "/product/*")]
+#[on_html("h1", title_handler)]
+#[on_page(product_page_handler, url = "http://example.com/product/*")]
+struct ShopScraper {
+    events: Vec<String>,
+}
+
+impl ShopScraper {
+    fn new() -> Self {
+        ShopScraper { events: vec![] }
+    }
+
+    async fn category_handler(&mut self, response: Response) -> Result<()> {
+        self.events.push(format!("category {}", response.url));
+        Ok(())
+    }
+
+    async fn product_handler(&mut self, response: Response) -> Result<()> {
+        self.events.push(format!("product {}", response.url));
+        Ok(())
+    }
+
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        response
+            .navigate(format!("http://example.com{}", href))
+            .await
+    }
+
+    async fn product_title_handler(&mut self, response: Response, h1: Element) -> Result<()> {
+        self.events.push(format!(
+            "product title {} {}",
+            response.url,
+            h1.text().unwrap()
+        ));
+        Ok(())
+    }
+
+    async fn title_handler(&mut self, response: Response, h1: Element) -> Result<()> {
+        self.events
+            .push(format!("title {} {}", response.url, h1.text().unwrap()));
+        Ok(())
+    }
+
+    async fn product_page_handler(&mut self, page: String) -> Result<()> {
+        self.events.push(format!("product page {}", page.len()));
+        Ok(())
+    }
+}
+
+fn site() -> MemoryFetcher {
+    MemoryFetcher::new()
+        .with_page(
+            "http://example.com/",
+            r#"<h1>Home</h1><a href="/ignored">x</a>"#,
+        )
+        .with_page(
+            "http://example.com/category/books",
+            r#"<h1>Books</h1><a href="/product/1">1</a>"#,
+        )
+        .with_page("http://example.com/product/1", "<h1>Book</h1>")
+}
+
+#[async_std::test]
+async fn test_url_handlers_and_scopes() {
+    let opts = Opts::new()
+        .with_urls(vec![
+            "http://example.com/",
+            "http://example.com/category/books",
+        ])
+        .with_threads(1);
+    let mut crabler = Crabler::with_fetcher(ShopScraper::new(), &opts, site());
+    let stats = crabler.start(&opts).await.unwrap();
+
+    let mut events = crabler.into_scraper().events;
+    events.sort();
+    assert_eq!(
+        events,
+        vec![
+            "category http://example.com/category/books",
+            "product http://example.com/product/1",
+            "product page 13",
+            "product title http://example.com/product/1 Book",
+            "title http://example.com/ Home",
+            "title http://example.com/category/books Books",
+        ],
+        "Links are followed only on category pages, first matching h1 handler wins"
+    );
+    assert_eq!(stats.pages(), 3);
+}
+
+#[test]
+fn test_url_matches() {
+    assert!(url_matches("/category/*", "http://example.com/category/books").unwrap());
+    assert!(!url_matches("/category/*", "http://example.com/category/books/2").unwrap());
+    assert!(url_matches("/category/**", "http://example.com/category/books/2").unwrap());
+    assert!(url_matches("/search?q=*", "http://example.com/search?q=rust").unwrap());
+    assert!(url_matches("http://*.example.com/", "http://www.example.com/").unwrap());
+    assert!(!url_matches("http://*.example.com/", "http://example.com/").unwrap());
+    assert!(url_matches("^/product/\\d+$", "http://example.com/product/12").unwrap());
+    assert!(!url_matches("^/product/\\d+$", "http://example.com/product/abc").unwrap());
+}
+
+#[test]
+fn test_compiled_url_pattern() {
+    let pattern = UrlPattern::new("/search?q=*").unwrap();
+    assert!(pattern.is_match("http://example.com/search?q=rust"));
+    assert!(
+        pattern.is_match("http://example.com/searchXq=rust"),
+        "? is a glob wildcard, not query separator"
+    );
+    assert!(!pattern.is_match("http://example.com/search?q=rust/2"));
+}
+
+#[test]
+fn test_invalid_url_pattern() {
+    match url_matches("^/product/(", "http://example.com/product/1") {
+        Err(CrablerError::UrlPattern(pattern, _)) => assert_eq!(pattern, "^/product/("),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
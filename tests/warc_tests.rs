@@ -0,0 +1,145 @@
+extern crate crabler;
+
+use crabler::*;
+
+#[derive(WebScraper)]
+#[on_html("a[href]", link_handler)]
+#[on_html("h1", title_handler)]
+struct TitleScraper {
+    base: String,
+    titles: Vec<(String, String)>,
+}
+
+impl TitleScraper {
+    fn new(base: &str) -> Self {
+        TitleScraper {
+            base: base.to_string(),
+            titles: vec![],
+        }
+    }
+
+    async fn link_handler(&mut self, mut response: Response, a: Element) -> Result<()> {
+        let href = a.attr("href").unwrap();
+        response.navigate(format!("{}{}", self.base, href)).await
+    }
+
+    async fn title_handler(&mut self, response: Response, h1: Element) -> Result<()> {
+        let path = response.url.trim_start_matches(&self.base).to_string();
+        self.titles.push((path, h1.text().unwrap()));
+        Ok(())
+    }
+}
+
+async fn new_mock_server() -> mockito::ServerGuard {
+    async_std::task::spawn_blocking(mockito::Server::new).await
+}
+
+async fn record_and_replay(archive: &str) {
+    let path = std::env::temp_dir().join(format!("crabler-{}-{}", std::process::id(), archive));
+    let path = path.to_string_lossy().to_string();
+
+    let mut server = new_mock_server().await;
+    let base = server.url();
+    let index = format!("{}/", base);
+    let _index = server
+        .mock("GET", "/")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body(r#"<h1>Index</h1><a href="/one">1</a><a href="/gone">2</a>"#)
+        .create();
+    let _one = server
+        .mock("GET", "/one")
+        .with_status(200)
+        .with_header("content-type", "text/html")
+        .with_body("<h1>One</h1>")
+        .create();
+    let _gone = server.mock("GET", "/gone").with_status(410).create();
+
+    let recorded = TitleScraper::new(&base)
+        .crawl(
+            Opts::new()
+                .with_urls(vec![index.as_str()])
+                .with_warc_output(&path),
+        )
+        .await
+        .unwrap()
+        .titles;
+    drop(server);
+
+    let opts = Opts::new().with_urls(vec![index.as_str()]);
+    let replay = WarcReplay::open(&path).unwrap();
+    let mut crabler = Crabler::with_fetcher(TitleScraper::new(&base), &opts, replay);
+    let stats = crabler.start(&opts).await.unwrap();
+
+    let mut replayed = crabler.into_scraper().titles;
+    replayed.sort();
+    let mut recorded = recorded;
+    recorded.sort();
+
+    assert_eq!(
+        replayed,
+        vec![
+            ("/".to_string(), "Index".to_string()),
+            ("/one".to_string(), "One".to_string())
+        ]
+    );
+    assert_eq!(replayed, recorded);
+    assert_eq!(stats.statuses.get(&410), Some(&1));
+
+    let mut derived = TitleScraper::new(&base)
+        .crawl(opts.with_warc_replay(&path))
+        .await
+        .unwrap()
+        .titles;
+    derived.sort();
+    assert_eq!(
+        derived, recorded,
+        "Derived entry points replay from options"
+    );
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[async_std::test]
+async fn test_record_and_replay() {
+    record_and_replay("crawl.warc").await;
+}
+
+#[async_std::test]
+async fn test_record_and_replay_gzipped() {
+    record_and_replay("crawl.warc.gz").await;
+}
+
+#[async_std::test]
+async fn test_warc_records() {
+    let path = std::env::temp_dir().join(format!("crabler-{}-records.warc", std::process::id()));
+    let path = path.to_string_lossy().to_string();
+    let fetcher = MemoryFetcher::new().with_page("http://example.com/?q=1", "<p>hi</p>");
+    let opts = Opts::new()
+        .with_urls(vec!["http://example.com/?q=1"])
+        .with_warc_output(&path);
+
+    let mut crabler = Crabler::with_fetcher(TitleScraper::new(""), &opts, fetcher);
+    crabler.start(&opts).await.unwrap();
+
+    let archive = std::fs::read_to_string(&path).unwrap();
+    let types = archive
+        .lines()
+        .filter(|line| line.starts_with("WARC-Type"))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        vec![
+            "WARC-Type: warcinfo",
+            "WARC-Type: request",
+            "WARC-Type: response"
+        ]
+    );
+    assert!(archive.contains("WARC-Target-URI: http://example.com/?q=1\r\n"));
+    assert!(archive.contains("GET /?q=1 HTTP/1.1\r\nHost: example.com\r\n"));
+    assert!(archive.contains(
+        "HTTP/1.1 200 OK\r\ncontent-type: text/html\r\nContent-Length: 9\r\n\r\n<p>hi</p>"
+    ));
+
+    std::fs::remove_file(&path).unwrap();
+}